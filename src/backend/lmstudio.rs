@@ -0,0 +1,335 @@
+use super::{
+    model_matches, openai, parse_percentage, GenerateRequest, GenerateResponse, LlmBackend,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tracing::info;
+
+/// LM Studio runtime: OpenAI-compatible API on `LMSTUDIO_URL` plus the `lms` CLI
+/// for server control and model management
+pub struct LmStudioBackend {
+    client: Client,
+    base_url: String,
+}
+
+impl LmStudioBackend {
+    pub fn new(client: Client, base_url: String) -> Self {
+        Self {
+            client,
+            base_url: openai::normalize_base_url(&base_url),
+        }
+    }
+
+    /// Strip an optional "@quant" suffix (e.g. "user/repo@Q4_K_M"). Only `lms get`
+    /// understands the quantization selector; `lms load` and the API take the bare name.
+    fn model_key(model: &str) -> &str {
+        model.split('@').next().unwrap_or(model)
+    }
+
+    /// Run an `lms` subcommand and return its stdout, failing on a non-zero exit
+    async fn run_lms(args: &[&str]) -> Result<String> {
+        let output = tokio::process::Command::new("lms")
+            .args(args)
+            .output()
+            .await
+            .with_context(|| format!("Failed to execute 'lms {}' command", args.join(" ")))?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "'lms {}' failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Parse the JSON printed by `lms ls --json` / `lms ps --json`.
+    /// Each entry may be known by its model key, its on-disk path ("user/repo/file.gguf")
+    /// or, for loaded models, its API identifier; all of them are returned.
+    fn parse_model_list(output: &str) -> Result<Vec<String>> {
+        let entries: Vec<serde_json::Value> = serde_json::from_str(output.trim())
+            .context("Unexpected output from lms (expected a JSON array)")?;
+
+        let mut names = Vec::new();
+        for entry in &entries {
+            for field in ["path", "modelKey", "identifier"] {
+                if let Some(name) = entry.get(field).and_then(|v| v.as_str()) {
+                    if !names.iter().any(|n| n == name) {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+        }
+
+        Ok(names)
+    }
+}
+
+#[async_trait]
+impl LlmBackend for LmStudioBackend {
+    fn name(&self) -> &'static str {
+        "LM Studio"
+    }
+
+    fn not_installed_message(&self) -> String {
+        "LM Studio CLI not found. Install from https://lmstudio.ai and enable CLI in settings."
+            .to_string()
+    }
+
+    fn check_installed(&self) -> Result<bool> {
+        let result = std::process::Command::new("lms").arg("version").output();
+
+        Ok(result.is_ok())
+    }
+
+    async fn is_running(&self) -> Result<bool> {
+        // LMSTUDIO_URL may point at another machine, so probe the API rather than
+        // asking the local `lms server status`
+        Ok(openai::is_reachable(&self.client, &self.base_url, None).await)
+    }
+
+    async fn start(&self) -> Result<bool> {
+        info!("Starting LM Studio server...");
+
+        Self::run_lms(&["server", "start"])
+            .await
+            .context("Try running 'lms server start' manually")?;
+
+        // `lms server start` returns once the server is up; confirm it answers requests
+        self.is_running().await
+    }
+
+    async fn generate(&self, request: &GenerateRequest) -> Result<GenerateResponse> {
+        let request = GenerateRequest {
+            model: Self::model_key(&request.model).to_string(),
+            ..request.clone()
+        };
+        openai::chat_completion(&self.client, &self.base_url, None, self.name(), &request).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        let output = Self::run_lms(&["ls", "--json"]).await?;
+        Self::parse_model_list(&output)
+    }
+
+    async fn is_loaded(&self, model: &str) -> Result<bool> {
+        let output = Self::run_lms(&["ps", "--json"]).await?;
+        let loaded = Self::parse_model_list(&output)?;
+        Ok(loaded.iter().any(|m| model_matches(m, model)))
+    }
+
+    async fn pull(&self, model: &str, on_progress: &mut (dyn FnMut(u8) + Send)) -> Result<()> {
+        info!("Pulling model...");
+
+        let mut child = tokio::process::Command::new("lms")
+            .args(["get", model, "--yes"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to execute 'lms get' command")?;
+
+        // `lms get` may report on either stream; read both at once so a full
+        // stderr pipe can't block the child while we wait on stdout
+        let stdout = child.stdout.take().expect("stdout was piped");
+        let stderr = child.stderr.take().expect("stderr was piped");
+        let mut stdout_lines = BufReader::new(stdout).lines();
+        let mut stderr_lines = BufReader::new(stderr).lines();
+        let (mut stdout_open, mut stderr_open) = (true, true);
+
+        let mut last_percent: u8 = 0;
+        let mut last_line = String::new();
+        let mut last_error_line = String::new();
+
+        while stdout_open || stderr_open {
+            let (line, from_stderr) = tokio::select! {
+                line = stdout_lines.next_line(), if stdout_open => (line?, false),
+                line = stderr_lines.next_line(), if stderr_open => (line?, true),
+            };
+
+            let Some(line) = line else {
+                if from_stderr {
+                    stderr_open = false;
+                } else {
+                    stdout_open = false;
+                }
+                continue;
+            };
+
+            if let Some(percent) = parse_percentage(&line) {
+                if percent > last_percent {
+                    last_percent = percent;
+                    on_progress(percent);
+                }
+            }
+            if !line.trim().is_empty() {
+                if from_stderr {
+                    last_error_line = line;
+                } else {
+                    last_line = line;
+                }
+            }
+        }
+
+        let status = child.wait().await?;
+
+        if !status.success() {
+            let detail = if last_error_line.is_empty() {
+                last_line
+            } else {
+                last_error_line
+            };
+
+            anyhow::bail!(
+                "Model '{}' not found. Download it in LM Studio or check MODEL_NAME.\nError: {}",
+                model,
+                detail
+            );
+        }
+
+        on_progress(100);
+        info!("Model pulled successfully");
+        Ok(())
+    }
+
+    async fn load(&self, model: &str) -> Result<()> {
+        info!("Loading model...");
+
+        Self::run_lms(&["load", Self::model_key(model), "--yes"])
+            .await
+            .with_context(|| {
+                format!(
+                    "Model '{}' not found. Download it in LM Studio or check MODEL_NAME.",
+                    model
+                )
+            })?;
+
+        info!("Model ready");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_model_key_strips_quantization() {
+        assert_eq!(
+            LmStudioBackend::model_key("qwen/qwen2.5-coder-32b-instruct-gguf@Q4_K_M"),
+            "qwen/qwen2.5-coder-32b-instruct-gguf"
+        );
+        assert_eq!(
+            LmStudioBackend::model_key("unsloth/SmolLM3-3B-128K-GGUF"),
+            "unsloth/SmolLM3-3B-128K-GGUF"
+        );
+    }
+
+    #[test]
+    fn test_parse_lms_ls_output() {
+        let output = r#"[
+            {
+                "type": "llm",
+                "modelKey": "smollm3-3b-128k",
+                "path": "unsloth/SmolLM3-3B-128K-GGUF/SmolLM3-3B-128K-Q4_K_M.gguf"
+            },
+            {
+                "type": "embedding",
+                "modelKey": "text-embedding-nomic-embed-text-v1.5",
+                "path": "nomic-ai/nomic-embed-text-v1.5-GGUF/nomic-embed-text-v1.5.Q4_K_M.gguf"
+            }
+        ]"#;
+
+        let models = LmStudioBackend::parse_model_list(output).unwrap();
+        assert!(models.contains(&"smollm3-3b-128k".to_string()));
+        assert!(models
+            .iter()
+            .any(|m| model_matches(m, "unsloth/SmolLM3-3B-128K-GGUF")));
+        assert!(!models
+            .iter()
+            .any(|m| model_matches(m, "bartowski/Other-GGUF")));
+    }
+
+    #[test]
+    fn test_parse_lms_ps_output() {
+        let output = r#"[{"identifier": "smollm3-3b-128k", "path": "unsloth/SmolLM3-3B-128K-GGUF/SmolLM3-3B-128K-Q4_K_M.gguf"}]"#;
+
+        let models = LmStudioBackend::parse_model_list(output).unwrap();
+        assert!(models
+            .iter()
+            .any(|m| model_matches(m, "unsloth/SmolLM3-3B-128K-GGUF@Q4_K_M")));
+    }
+
+    #[test]
+    fn test_parse_lms_output_empty_and_invalid() {
+        assert!(LmStudioBackend::parse_model_list("[]").unwrap().is_empty());
+        assert!(LmStudioBackend::parse_model_list("No models found").is_err());
+    }
+
+    #[test]
+    fn test_base_url_normalization() {
+        let backend = LmStudioBackend::new(Client::new(), "http://localhost:1234/v1".to_string());
+        assert_eq!(backend.base_url, "http://localhost:1234");
+
+        let backend = LmStudioBackend::new(Client::new(), "http://localhost:1234/".to_string());
+        assert_eq!(backend.base_url, "http://localhost:1234");
+    }
+
+    #[tokio::test]
+    async fn test_is_running_probes_models_endpoint() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": []
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let backend = LmStudioBackend::new(Client::new(), mock_server.uri());
+        assert!(backend.is_running().await.unwrap());
+
+        let stopped = LmStudioBackend::new(Client::new(), "http://localhost:99999".to_string());
+        assert!(!stopped.is_running().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_generate_uses_chat_completions() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "qwen/qwen2.5-coder-32b-instruct-gguf",
+                "messages": [{"role": "user", "content": "classify this"}],
+                "max_tokens": 100
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "2"}}]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let backend = LmStudioBackend::new(Client::new(), mock_server.uri());
+        let response = backend
+            .generate(&GenerateRequest {
+                model: "qwen/qwen2.5-coder-32b-instruct-gguf@Q4_K_M".to_string(),
+                prompt: "classify this".to_string(),
+                temperature: 0.1,
+                max_tokens: 100,
+                think: false,
+                format: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(response.text, "2");
+    }
+}
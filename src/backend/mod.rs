@@ -0,0 +1,210 @@
+// LLM runtime backends.
+//
+// `ModelManager` owns prompt building and response parsing; everything that talks to a
+// concrete runtime (HTTP endpoints, CLI binaries) lives behind the `LlmBackend` trait.
+mod lmstudio;
+mod ollama;
+mod openai;
+
+pub use lmstudio::LmStudioBackend;
+pub use ollama::OllamaBackend;
+pub use openai::OpenAiBackend;
+
+use crate::types::{Backend, Config};
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+
+/// A single non-streaming generation request
+#[derive(Debug, Clone)]
+pub struct GenerateRequest {
+    /// Backend-specific model identifier (see `Config::effective_model_name`)
+    pub model: String,
+    pub prompt: String,
+    pub temperature: f32,
+    /// Upper bound on generated tokens
+    pub max_tokens: i32,
+    /// Enable thinking/reasoning mode for models that support it
+    pub think: bool,
+    /// JSON schema the reply must conform to (structured outputs), if any
+    pub format: Option<serde_json::Value>,
+}
+
+/// Text produced by a backend for a `GenerateRequest`
+#[derive(Debug, Clone)]
+pub struct GenerateResponse {
+    pub text: String,
+    /// Thinking/reasoning trace from models that support it
+    pub thinking: Option<String>,
+}
+
+/// Errors a backend reports in a form callers can act on
+#[derive(Debug, thiserror::Error)]
+pub enum BackendError {
+    /// The server rejected the request's `format` schema (e.g. an older runtime or a
+    /// server without structured output support). Retrying without a schema may succeed.
+    #[error("{backend} does not support structured output ({status})")]
+    StructuredOutputUnsupported {
+        backend: &'static str,
+        status: reqwest::StatusCode,
+    },
+    /// The runtime is down or the model is not loaded; the message says how to fix it
+    #[error("{0}")]
+    NotReady(String),
+}
+
+impl BackendError {
+    /// Classify a failed response to a request that carried a `format` schema. Only an
+    /// error body that names the format or schema counts, so unrelated 400s (context
+    /// length, invalid options) are not mistaken for missing structured output support.
+    pub(crate) fn rejected_format(
+        backend: &'static str,
+        request: &GenerateRequest,
+        status: reqwest::StatusCode,
+        body: &str,
+    ) -> Option<Self> {
+        let unsupported = matches!(status.as_u16(), 400 | 422 | 501);
+        let body = body.to_lowercase();
+        let mentions_format = body.contains("format") || body.contains("schema");
+        (request.format.is_some() && unsupported && mentions_format)
+            .then_some(BackendError::StructuredOutputUnsupported { backend, status })
+    }
+}
+
+/// A local LLM runtime that can serve tagging requests.
+#[async_trait]
+pub trait LlmBackend: Send + Sync {
+    /// Human-readable runtime name used in status and error messages (e.g. "Ollama")
+    fn name(&self) -> &'static str;
+
+    /// Message returned by `init_llm` when the runtime is not installed
+    fn not_installed_message(&self) -> String;
+
+    /// Check whether the runtime is installed on this machine
+    fn check_installed(&self) -> Result<bool>;
+
+    /// Whether the runtime downloads and loads models itself. When false, `init_llm`
+    /// skips the pull/load steps because the user's server already serves a model.
+    fn manages_models(&self) -> bool {
+        true
+    }
+
+    /// Check whether the runtime's server is reachable
+    async fn is_running(&self) -> Result<bool>;
+
+    /// Start the runtime's server. Returns true once it is ready to accept requests.
+    async fn start(&self) -> Result<bool>;
+
+    /// Run a single completion
+    async fn generate(&self, request: &GenerateRequest) -> Result<GenerateResponse>;
+
+    /// List the models downloaded into the runtime
+    async fn list_models(&self) -> Result<Vec<String>>;
+
+    /// Check whether the runtime's model library knows a model name, even if the model
+    /// is not downloaded. `None` when the runtime has no library to ask.
+    async fn check_model_name_valid(&self, _model: &str) -> Result<Option<bool>> {
+        Ok(None)
+    }
+
+    /// Check whether the model is currently loaded into memory
+    async fn is_loaded(&self, model: &str) -> Result<bool>;
+
+    /// Download a model, reporting progress as a percentage (0-100)
+    async fn pull(&self, model: &str, on_progress: &mut (dyn FnMut(u8) + Send)) -> Result<()>;
+
+    /// Load a downloaded model into memory
+    async fn load(&self, model: &str) -> Result<()>;
+}
+
+/// Create the backend selected in `Config`
+pub fn create_backend(config: &Config, client: Client) -> Box<dyn LlmBackend> {
+    match config.backend {
+        Backend::Ollama => Box::new(OllamaBackend::new(
+            client,
+            config.ollama_url.clone(),
+            config.model_source.clone(),
+        )),
+        Backend::LmStudio => Box::new(LmStudioBackend::new(client, config.lmstudio_url.clone())),
+        Backend::OpenAi => Box::new(OpenAiBackend::new(
+            client,
+            config.openai_base_url.clone(),
+            config.openai_api_key.clone(),
+        )),
+    }
+}
+
+/// Check whether a model name reported by a runtime refers to the wanted model.
+/// Matches exact names, or any tag/quantization of the same base model
+/// (e.g. "llama3" matches "llama3:8b", "user/repo@Q4_K_M" matches "user/repo/file.gguf").
+/// The comparison is case-insensitive since LM Studio lowercases some model keys.
+pub(crate) fn model_matches(candidate: &str, wanted: &str) -> bool {
+    let candidate = candidate.to_lowercase();
+    let wanted = wanted.to_lowercase();
+    let wanted_base = wanted.split([':', '@']).next().unwrap_or("");
+    candidate == wanted || candidate.starts_with(wanted_base)
+}
+
+/// Parse percentage from a CLI progress line.
+/// Runtimes output progress like "pulling abc123... 45%" or just contain a percentage.
+pub(crate) fn parse_percentage(line: &str) -> Option<u8> {
+    // Look for pattern like "45%" in the line
+    for word in line.split_whitespace() {
+        if word.ends_with('%') {
+            if let Ok(num) = word.trim_end_matches('%').parse::<u8>() {
+                return Some(num.min(100));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_matches_exact() {
+        assert!(model_matches("qwen2.5-coder:7b", "qwen2.5-coder:7b"));
+    }
+
+    #[test]
+    fn test_model_matches_base_name() {
+        assert!(model_matches("qwen2.5-coder:latest", "qwen2.5-coder:7b"));
+        assert!(model_matches("llama3:8b", "llama3"));
+        assert!(!model_matches("mistral:latest", "llama3"));
+    }
+
+    #[test]
+    fn test_model_matches_quantization_and_case() {
+        assert!(model_matches(
+            "unsloth/smollm3-3b-128k-gguf/smollm3-3b-128k-q4_k_m.gguf",
+            "unsloth/SmolLM3-3B-128K-GGUF@Q4_K_M"
+        ));
+    }
+
+    #[test]
+    fn test_parse_percentage_basic() {
+        assert_eq!(parse_percentage("45%"), Some(45));
+        assert_eq!(parse_percentage("100%"), Some(100));
+        assert_eq!(parse_percentage("0%"), Some(0));
+    }
+
+    #[test]
+    fn test_parse_percentage_with_text() {
+        assert_eq!(parse_percentage("pulling abc123... 67%"), Some(67));
+        assert_eq!(parse_percentage("downloading model 89%"), Some(89));
+    }
+
+    #[test]
+    fn test_parse_percentage_no_percent() {
+        assert_eq!(parse_percentage("pulling manifest"), None);
+        assert_eq!(parse_percentage("verifying"), None);
+        assert_eq!(parse_percentage(""), None);
+    }
+
+    #[test]
+    fn test_parse_percentage_caps_at_100() {
+        assert_eq!(parse_percentage("150%"), Some(100));
+    }
+}
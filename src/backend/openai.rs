@@ -0,0 +1,435 @@
+// Shared client for OpenAI-compatible `/v1/chat/completions` and `/v1/models` endpoints,
+// plus the generic backend for servers the user runs themselves.
+use super::{model_matches, BackendError, GenerateRequest, GenerateResponse, LlmBackend};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+struct ChatCompletionRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    stream: bool,
+    temperature: f32,
+    max_tokens: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatResponseMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatResponseMessage {
+    #[serde(default)]
+    content: Option<String>,
+    /// Reasoning trace returned by some servers for thinking models
+    #[serde(default)]
+    reasoning_content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ModelsResponse {
+    data: Vec<ModelEntry>,
+}
+
+#[derive(Deserialize)]
+struct ModelEntry {
+    id: String,
+}
+
+/// Send a single-message chat completion and return the assistant's reply
+pub(super) async fn chat_completion(
+    client: &Client,
+    base_url: &str,
+    api_key: Option<&str>,
+    backend_name: &'static str,
+    request: &GenerateRequest,
+) -> Result<GenerateResponse> {
+    let body = ChatCompletionRequest {
+        model: request.model.clone(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: request.prompt.clone(),
+        }],
+        stream: false,
+        temperature: request.temperature,
+        max_tokens: request.max_tokens,
+        response_format: request.format.as_ref().map(|schema| {
+            serde_json::json!({
+                "type": "json_schema",
+                "json_schema": {"name": "response", "strict": true, "schema": schema}
+            })
+        }),
+    };
+
+    let mut http_request = client
+        .post(format!("{}/v1/chat/completions", base_url))
+        .json(&body);
+    if let Some(key) = api_key {
+        http_request = http_request.bearer_auth(key);
+    }
+
+    let response = http_request
+        .send()
+        .await
+        .with_context(|| format!("Failed to send request to {}", backend_name))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        if let Some(err) = BackendError::rejected_format(backend_name, request, status, &body) {
+            return Err(err.into());
+        }
+        anyhow::bail!("{} request failed: {} {}", backend_name, status, body);
+    }
+
+    let data: ChatCompletionResponse = response
+        .json()
+        .await
+        .with_context(|| format!("Failed to parse {} response", backend_name))?;
+
+    let message = data
+        .choices
+        .into_iter()
+        .next()
+        .map(|c| c.message)
+        .with_context(|| format!("{} returned no choices", backend_name))?;
+
+    Ok(GenerateResponse {
+        text: message.content.unwrap_or_default(),
+        thinking: message.reasoning_content,
+    })
+}
+
+/// List the model ids served at `/v1/models`
+pub(super) async fn list_model_ids(
+    client: &Client,
+    base_url: &str,
+    api_key: Option<&str>,
+) -> Result<Vec<String>> {
+    let mut http_request = client.get(format!("{}/v1/models", base_url));
+    if let Some(key) = api_key {
+        http_request = http_request.bearer_auth(key);
+    }
+
+    let response = http_request.send().await?;
+    if !response.status().is_success() {
+        anyhow::bail!("Listing models failed: {}", response.status());
+    }
+
+    let data: ModelsResponse = response.json().await?;
+    Ok(data.data.into_iter().map(|m| m.id).collect())
+}
+
+/// Accept base URLs given with or without the "/v1" suffix
+pub(super) fn normalize_base_url(base_url: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    base_url.strip_suffix("/v1").unwrap_or(base_url).to_string()
+}
+
+/// Check whether an OpenAI-compatible server answers `/v1/models`
+pub(super) async fn is_reachable(client: &Client, base_url: &str, api_key: Option<&str>) -> bool {
+    let mut http_request = client.get(format!("{}/v1/models", base_url));
+    if let Some(key) = api_key {
+        http_request = http_request.bearer_auth(key);
+    }
+
+    match http_request.send().await {
+        Ok(resp) => resp.status().is_success(),
+        Err(_) => false,
+    }
+}
+
+/// Generic OpenAI-compatible server (llama.cpp `llama-server`, vLLM, LocalAI, ...).
+/// The user starts the server and chooses its model, so there is nothing to
+/// install, start, pull or load from here.
+pub struct OpenAiBackend {
+    client: Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl OpenAiBackend {
+    pub fn new(client: Client, base_url: String, api_key: Option<String>) -> Self {
+        Self {
+            client,
+            base_url: normalize_base_url(&base_url),
+            api_key,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmBackend for OpenAiBackend {
+    fn name(&self) -> &'static str {
+        "OpenAI-compatible server"
+    }
+
+    fn not_installed_message(&self) -> String {
+        // Never shown: there is no local runtime to detect
+        "OpenAI-compatible server not found. Check OPENAI_BASE_URL.".to_string()
+    }
+
+    fn check_installed(&self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn manages_models(&self) -> bool {
+        false
+    }
+
+    async fn is_running(&self) -> Result<bool> {
+        Ok(is_reachable(&self.client, &self.base_url, self.api_key.as_deref()).await)
+    }
+
+    async fn start(&self) -> Result<bool> {
+        anyhow::bail!(
+            "no server is answering at {}. Start it manually or check OPENAI_BASE_URL.",
+            self.base_url
+        )
+    }
+
+    async fn generate(&self, request: &GenerateRequest) -> Result<GenerateResponse> {
+        chat_completion(
+            &self.client,
+            &self.base_url,
+            self.api_key.as_deref(),
+            self.name(),
+            request,
+        )
+        .await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        list_model_ids(&self.client, &self.base_url, self.api_key.as_deref()).await
+    }
+
+    async fn is_loaded(&self, model: &str) -> Result<bool> {
+        // Whatever the server lists is what it serves
+        let models = self.list_models().await?;
+        Ok(models.iter().any(|m| model_matches(m, model)))
+    }
+
+    async fn pull(&self, _model: &str, _on_progress: &mut (dyn FnMut(u8) + Send)) -> Result<()> {
+        anyhow::bail!("{} does not support downloading models", self.name())
+    }
+
+    async fn load(&self, _model: &str) -> Result<()> {
+        anyhow::bail!("{} does not support loading models", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_chat_completion_request_serialization() {
+        let request = ChatCompletionRequest {
+            model: "unsloth/SmolLM3-3B-128K-GGUF".to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "test prompt".to_string(),
+            }],
+            stream: false,
+            temperature: 0.1,
+            max_tokens: 100,
+            response_format: None,
+        };
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["model"], "unsloth/SmolLM3-3B-128K-GGUF");
+        assert_eq!(json["messages"][0]["role"], "user");
+        assert_eq!(json["messages"][0]["content"], "test prompt");
+        assert_eq!(json["stream"], false);
+        assert_eq!(json["max_tokens"], 100);
+        assert!(json.get("response_format").is_none());
+    }
+
+    #[test]
+    fn test_chat_completion_response_parsing() {
+        let json = r#"{
+            "choices": [
+                {"message": {"role": "assistant", "content": "2", "reasoning_content": "auth"}}
+            ]
+        }"#;
+
+        let parsed: ChatCompletionResponse = serde_json::from_str(json).unwrap();
+        let message = &parsed.choices[0].message;
+        assert_eq!(message.content.as_deref(), Some("2"));
+        assert_eq!(message.reasoning_content.as_deref(), Some("auth"));
+    }
+
+    #[test]
+    fn test_base_url_normalization() {
+        let backend =
+            OpenAiBackend::new(Client::new(), "http://localhost:8000/v1/".to_string(), None);
+        assert_eq!(backend.base_url, "http://localhost:8000");
+
+        let backend = OpenAiBackend::new(Client::new(), "http://localhost:8080".to_string(), None);
+        assert_eq!(backend.base_url, "http://localhost:8080");
+    }
+
+    #[tokio::test]
+    async fn test_list_models_sends_bearer_token() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "data": [{"id": "Qwen/Qwen2.5-3B-Instruct", "object": "model"}]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let backend = OpenAiBackend::new(Client::new(), mock_server.uri(), Some("secret".into()));
+        assert!(backend.is_running().await.unwrap());
+        assert_eq!(
+            backend.list_models().await.unwrap(),
+            vec!["Qwen/Qwen2.5-3B-Instruct"]
+        );
+        assert!(backend.is_loaded("Qwen/Qwen2.5-3B-Instruct").await.unwrap());
+
+        // Without the token the server rejects the probe
+        let anonymous = OpenAiBackend::new(Client::new(), mock_server.uri(), None);
+        assert!(!anonymous.is_running().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_generate_uses_chat_completions() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "Qwen/Qwen2.5-3B-Instruct",
+                "messages": [{"role": "user", "content": "classify this"}]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "1, 2"}}]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let backend = OpenAiBackend::new(Client::new(), mock_server.uri(), Some("secret".into()));
+        let response = backend
+            .generate(&GenerateRequest {
+                model: "Qwen/Qwen2.5-3B-Instruct".to_string(),
+                prompt: "classify this".to_string(),
+                temperature: 0.1,
+                max_tokens: 100,
+                think: false,
+                format: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(response.text, "1, 2");
+    }
+
+    #[test]
+    fn test_does_not_manage_models() {
+        let backend = OpenAiBackend::new(Client::new(), "http://localhost:8080".to_string(), None);
+        assert!(backend.check_installed().unwrap());
+        assert!(!backend.manages_models());
+    }
+
+    #[tokio::test]
+    async fn test_generate_sends_json_schema_response_format() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "response_format": {
+                    "type": "json_schema",
+                    "json_schema": {"schema": {"type": "object"}}
+                }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "{\"tags\": []}"}}]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(422).set_body_json(serde_json::json!({
+                "error": {"message": "response_format json_schema is not supported"}
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let backend = OpenAiBackend::new(Client::new(), mock_server.uri(), None);
+        let mut request = GenerateRequest {
+            model: "local-model".to_string(),
+            prompt: "classify this".to_string(),
+            temperature: 0.1,
+            max_tokens: 100,
+            think: false,
+            format: Some(serde_json::json!({"type": "object"})),
+        };
+        let response = backend.generate(&request).await.unwrap();
+        assert_eq!(response.text, "{\"tags\": []}");
+
+        // A rejected schema is reported as unsupported structured output
+        request.format = Some(serde_json::json!({"type": "array"}));
+        let err = backend.generate(&request).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BackendError>(),
+            Some(BackendError::StructuredOutputUnsupported { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_generate_keeps_context_length_error() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "error": {
+                    "message": "This model's maximum context length is 8192 tokens",
+                    "code": "context_length_exceeded"
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let backend = OpenAiBackend::new(Client::new(), mock_server.uri(), None);
+        let err = backend
+            .generate(&GenerateRequest {
+                model: "local-model".to_string(),
+                prompt: "classify this".to_string(),
+                temperature: 0.1,
+                max_tokens: 100,
+                think: false,
+                format: Some(serde_json::json!({"type": "object"})),
+            })
+            .await
+            .unwrap_err();
+
+        assert!(err.downcast_ref::<BackendError>().is_none());
+        assert!(err.to_string().contains("context length"));
+    }
+}
@@ -1,49 +1,241 @@
+use crate::discovery::{self, ConfigSources};
+use crate::extends;
 use crate::model_manager::ModelManager;
+use crate::reload::{ConfigDiff, ConfigSet};
 use crate::rules;
 use crate::types::*;
+use crate::validation;
 use anyhow::Result;
-use tracing::info;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
 
-/// Match info from rule evaluation
-struct RuleMatchInfo {
+/// A leaf condition that matched during rule evaluation
+#[derive(PartialEq)]
+struct MatchedCondition {
     trigger_type: String,
     trigger_value: String,
 }
 
+impl MatchedCondition {
+    fn is_negation(&self) -> bool {
+        matches!(self.trigger_type.as_str(), "not" | "none_of")
+    }
+}
+
+/// Outcome of evaluating conditions
+enum Evaluation {
+    Match(RuleMatchInfo),
+    NoMatch,
+    /// Depends on `llm_tag` conditions that were not evaluated
+    Unknown,
+}
+
+/// Match info from rule evaluation
+struct RuleMatchInfo {
+    /// Every condition that contributed to the match, in rule order
+    conditions: Vec<MatchedCondition>,
+    /// 100 for deterministic conditions, the LLM's confidence for tag conditions
+    confidence: u8,
+}
+
+impl RuleMatchInfo {
+    fn leaf(trigger_type: &str, trigger_value: &str, confidence: u8) -> Self {
+        Self {
+            conditions: vec![MatchedCondition {
+                trigger_type: trigger_type.to_string(),
+                trigger_value: trigger_value.to_string(),
+            }],
+            confidence,
+        }
+    }
+
+    /// Match produced by a satisfied `not`/`none_of`; the value is the excluded condition(s)
+    fn negation(trigger_type: &str, excluded: &impl serde::Serialize) -> Self {
+        Self::leaf(
+            trigger_type,
+            &serde_json::to_string(excluded).unwrap_or_default(),
+            100,
+        )
+    }
+
+    /// Add the conditions of another matching branch, skipping duplicates
+    fn merge_conditions(&mut self, other: RuleMatchInfo) {
+        for condition in other.conditions {
+            if !self.conditions.contains(&condition) {
+                self.conditions.push(condition);
+            }
+        }
+    }
+
+    /// One trigger per matched condition. Positive conditions explain a match better
+    /// than exclusions, so they are listed first.
+    fn into_triggers(self, rule: &str, files: &[String]) -> Vec<Trigger> {
+        let (positive, negations): (Vec<_>, Vec<_>) =
+            self.conditions.into_iter().partition(|c| !c.is_negation());
+
+        positive
+            .into_iter()
+            .chain(negations)
+            .map(|c| Trigger {
+                rule: rule.to_string(),
+                name: c.trigger_type,
+                description: c.trigger_value,
+                files: files.to_vec(),
+            })
+            .collect()
+    }
+}
+
 pub struct Classifier {
     pub model_manager: ModelManager,
-    user_config: UserConfig,
-    tag_config: LlmTagConfig,
-    rules_config: RulesConfig,
+    routing_mode: RoutingMode,
+    strict_config: bool,
+    /// Swapped as a unit by `reload_configs`
+    configs: RwLock<ConfigSet>,
+    /// Where config discovery starts; the working directory when empty
+    search_roots: RwLock<Vec<PathBuf>>,
 }
 
 impl Classifier {
     pub fn new(config: Config) -> Result<Self> {
+        let routing_mode = config.routing_mode.clone();
+        let strict_config = config.strict_config;
+        let search_roots = config.config_search_roots.clone();
         let model_manager = ModelManager::new(config)?;
         Ok(Self {
             model_manager,
-            user_config: UserConfig { agents: vec![] },
-            tag_config: LlmTagConfig { tags: vec![] },
-            rules_config: RulesConfig { rules: vec![] },
+            routing_mode,
+            strict_config,
+            configs: RwLock::new(ConfigSet::empty()),
+            search_roots: RwLock::new(search_roots),
         })
     }
 
     pub async fn initialize(&mut self) -> Result<()> {
-        self.model_manager.initialize().await?;
+        let sources = ConfigSources::discover(&self.search_roots());
+        self.initialize_from(sources).await
+    }
+
+    /// Like `initialize`, but load the startup configs from the given sources instead of
+    /// discovering them
+    pub async fn initialize_from(&mut self, sources: ConfigSources) -> Result<()> {
+        match self.routing_mode {
+            // Checked by `ensure_ready` once a request has tags left for the LLM to decide
+            RoutingMode::Hybrid => info!(
+                "Hybrid routing: {} is checked when a request needs LLM tags",
+                self.model_manager.backend_name()
+            ),
+            RoutingMode::RulesOnly => info!("Rules-only routing: skipping LLM initialization"),
+            RoutingMode::Auto => {
+                if let Err(e) = self.model_manager.initialize().await {
+                    warn!("LLM unavailable, routing will degrade to rules only: {}", e);
+                }
+            }
+        }
 
         // Load and cache configs on startup
-        self.user_config = Self::load_user_config_static()?;
-        self.tag_config = Self::load_tag_config_static()?;
-        self.rules_config = Self::load_rules_config_static()?;
+        let configs = Self::load_sources(sources, self.strict_config)?;
+        *self.configs.get_mut().unwrap_or_else(|e| e.into_inner()) = configs;
+
+        Ok(())
+    }
+
+    /// Discover, load and cross-check the startup configs
+    pub fn load_configs(strict_config: bool, search_roots: &[PathBuf]) -> Result<ConfigSet> {
+        Self::load_sources(ConfigSources::discover(search_roots), strict_config)
+    }
+
+    /// Load and cross-check the configs from the given sources
+    pub fn load_sources(sources: ConfigSources, strict_config: bool) -> Result<ConfigSet> {
+        let user_config = match sources.agents.path() {
+            Some(path) => rules::load_user_config(&path.to_string_lossy())?,
+            None => rules::default_user_config()?,
+        };
+        let tag_config = match sources.llm_tags.path() {
+            Some(path) => rules::load_llm_tag_config(&path.to_string_lossy())?,
+            None => rules::default_llm_tag_config()?,
+        };
+        let rules_config = match sources.rules.path() {
+            Some(path) => rules::load_rules_config(&path.to_string_lossy())?,
+            None => rules::default_rules_config()?,
+        };
 
         info!(
-            "Configs loaded: {} agents, {} tags, {} rules",
-            self.user_config.agents.len(),
-            self.tag_config.tags.len(),
-            self.rules_config.rules.len()
+            "Configs loaded: {} agents, {} tags, {} rules ({})",
+            user_config.agents.len(),
+            tag_config.tags.len(),
+            rules_config.rules.len(),
+            sources
         );
 
-        Ok(())
+        validation::check_configs(&user_config, &tag_config, &rules_config, strict_config)?;
+
+        let base_files = [&sources.agents, &sources.llm_tags, &sources.rules]
+            .iter()
+            .filter_map(|source| source.path())
+            .flat_map(|path| extends::layer_files(path).into_iter().skip(1))
+            .collect();
+
+        Ok(ConfigSet {
+            sources,
+            base_files,
+            ..ConfigSet::new(user_config, tag_config, rules_config)
+        })
+    }
+
+    /// Reload the startup configs and swap them in if every file loads and validates.
+    /// On error the current configs are kept. Requests in flight finish with the
+    /// configs they started with.
+    pub fn reload_configs(&self) -> Result<ConfigDiff> {
+        let configs = Self::load_configs(self.strict_config, &self.search_roots())?;
+        Ok(self.swap_configs(configs))
+    }
+
+    /// Start config discovery from new roots (the client's MCP roots) and reload.
+    /// On error the current roots and configs are kept.
+    pub fn set_search_roots(&self, search_roots: Vec<PathBuf>) -> Result<ConfigDiff> {
+        let configs = Self::load_configs(self.strict_config, &search_roots)?;
+        *self.search_roots.write().unwrap_or_else(|e| e.into_inner()) = search_roots;
+        Ok(self.swap_configs(configs))
+    }
+
+    fn swap_configs(&self, configs: ConfigSet) -> ConfigDiff {
+        let mut current = self.configs.write().unwrap_or_else(|e| e.into_inner());
+        let diff = ConfigDiff::between(&current, &configs);
+        *current = configs;
+        diff
+    }
+
+    fn search_roots(&self) -> Vec<PathBuf> {
+        self.search_roots
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Where each of the current configs was loaded from
+    pub fn config_sources(&self) -> ConfigSources {
+        self.configs
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .sources
+            .clone()
+    }
+
+    /// Files that can change the startup configs: the files they were loaded from, their
+    /// `extends` bases and any higher-priority location that does not exist yet
+    pub fn config_paths(&self) -> Vec<PathBuf> {
+        let mut paths = discovery::watch_paths(&self.search_roots());
+        paths.extend(
+            self.configs
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .base_files
+                .iter()
+                .cloned(),
+        );
+        paths
     }
 
     /// Classify a request and determine which agents should handle it.
@@ -80,36 +272,11 @@ impl Classifier {
             .validate()
             .map_err(|e| anyhow::anyhow!("Input validation failed: {}", e))?;
 
-        // Use cached configs (loaded on startup)
-        // Note: If user provides custom paths in input, load those instead
-        let user_config;
-        let tag_config;
-        let rules_config;
-
-        // User config loading (not currently used, but kept for potential future use)
-        let _user_config_ref = if let Some(ref path) = input.agent_config_path {
-            info!("Loading agent config from request path: {}", path);
-            user_config = rules::load_user_config(path)?;
-            &user_config
-        } else {
-            &self.user_config
-        };
-
-        let tag_config_ref = if let Some(ref path) = input.llm_tags_path {
-            info!("Loading LLM tag config from request path: {}", path);
-            tag_config = rules::load_llm_tag_config(path)?;
-            &tag_config
-        } else {
-            &self.tag_config
-        };
-
-        let rules_config_ref = if let Some(ref path) = input.rules_config_path {
-            info!("Loading rules config from request path: {}", path);
-            rules_config = rules::load_rules_config(path)?;
-            &rules_config
-        } else {
-            &self.rules_config
-        };
+        // Use cached configs (loaded on startup) unless the request provides custom paths.
+        // The user config is not currently used, but overrides are still validated.
+        let (_user_config, tag_config, rules_config) = self.resolve_configs(input)?;
+        let tag_config_ref: &LlmTagConfig = &tag_config;
+        let rules_config_ref: &RulesConfig = &rules_config;
 
         // Step 1: Check rule-based matches (fast path)
         let rule_based_agents = rules::apply_rules(input, rules_config_ref);
@@ -139,6 +306,7 @@ impl Classifier {
             .identify_tags(input, tag_config_ref)
             .await?;
         info!("LLM identified tags: {:?}", llm_tags);
+        let llm_tags: Vec<String> = llm_tags.into_iter().map(|t| t.tag).collect();
 
         // Step 3: Apply tag-based rules
         let tag_based_agents = rules::apply_llm_tag_rules(&llm_tags, rules_config_ref);
@@ -151,6 +319,10 @@ impl Classifier {
             }
         }
 
+        // Tag-based rules can re-add agents that a suppression rule removed
+        let suppressed = rules::suppressed_agents(input, &llm_tags, rules_config_ref);
+        all_agents.retain(|agent| !suppressed.contains(agent));
+
         // Return results (no LLM fallback - empty is valid)
         info!("Rules matched {} agents", all_agents.len());
         Ok(ClassificationResult {
@@ -173,11 +345,15 @@ impl Classifier {
     /// including trigger info, context with files/confidence/priority, and agent details.
     ///
     /// **Flow**:
-    /// 1. LLM semantic tagging - identify tags for the request
-    /// 2. Run ALL rules (file patterns, regex, branch patterns, AND tag-based rules)
-    /// 3. Return results (no LLM fallback - if no rules match, return empty)
+    /// 1. Pre-evaluate rules without tags to find which `llm_tag` conditions still matter
+    /// 2. LLM semantic tagging - only for those tags, and only if there are any
+    /// 3. Run ALL rules (file patterns, regex, branch patterns, AND tag-based rules)
+    /// 4. Return results (no LLM fallback - if no rules match, return empty)
     ///
     /// This is a pure rules-based router. The LLM only identifies tags, never picks agents.
+    ///
+    /// In `RoutingMode::RulesOnly`, or in `RoutingMode::Auto` when tagging fails, step 1 is
+    /// skipped: `llm_tag` conditions count as unmatched and the response is marked degraded.
     pub async fn classify_enhanced(
         &self,
         input: &ClassificationInput,
@@ -187,292 +363,864 @@ impl Classifier {
             .validate()
             .map_err(|e| anyhow::anyhow!("Input validation failed: {}", e))?;
 
-        // Use cached configs (loaded on startup)
-        let user_config;
-        let tag_config;
-        let rules_config;
-
-        let user_config_ref = if let Some(ref path) = input.agent_config_path {
-            info!("Loading agent config from request path: {}", path);
-            user_config = rules::load_user_config(path)?;
-            &user_config
-        } else {
-            &self.user_config
+        let (user_config, tag_config, rules_config) = self.resolve_configs(input)?;
+
+        // Steps 1-2: Pre-evaluate rules, then tag only what can still change an outcome
+        let llm = self
+            .tag_for_rules(input, &tag_config, &rules_config)
+            .await?;
+
+        // Step 3: Run ALL rules (file patterns, regex, branch patterns, AND tag-based)
+        let (instructions, suppressed, shadow) =
+            self.apply_all_rules_with_details(input, llm.known_tags(), &rules_config, &user_config);
+
+        info!(
+            "Rules matched {} agents ({} suppressed, {} shadow rules matched)",
+            instructions.len(),
+            suppressed.len(),
+            shadow.len()
+        );
+
+        // Step 4: Return results (no fallback - empty is valid)
+        Ok(InstructionsResponse {
+            instructions,
+            degraded: llm.degraded_reason.is_some(),
+            degraded_reason: llm.degraded_reason,
+            suppressed,
+            shadow,
+        })
+    }
+
+    /// Run the same pipeline as `classify_enhanced` and explain the outcome: the LLM
+    /// exchange, every rule's evaluated condition tree, and why referenced agents
+    /// are missing from the response.
+    pub async fn explain_routing(&self, input: &ClassificationInput) -> Result<RoutingExplanation> {
+        input
+            .validate()
+            .map_err(|e| anyhow::anyhow!("Input validation failed: {}", e))?;
+
+        let (user_config, tag_config, rules_config) = self.resolve_configs(input)?;
+        let llm = self
+            .tag_for_rules(input, &tag_config, &rules_config)
+            .await?;
+
+        let (instructions, suppressed, shadow) =
+            self.apply_all_rules_with_details(input, llm.known_tags(), &rules_config, &user_config);
+        let response = InstructionsResponse {
+            instructions,
+            degraded: llm.degraded_reason.is_some(),
+            degraded_reason: llm.degraded_reason.clone(),
+            suppressed,
+            shadow,
+        };
+
+        let rules: Vec<RuleTrace> = rules_config
+            .rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.enabled)
+            .map(|(idx, rule)| {
+                let conditions = self.trace_rule(&rule.conditions, input, llm.known_tags());
+                RuleTrace {
+                    rule: rule.display_name(idx),
+                    mode: rule.mode,
+                    matched: conditions.result,
+                    conditions,
+                    route_to_subagents: rule.route_to_subagents.clone(),
+                    suppress_subagents: rule.suppress_subagents.clone(),
+                }
+            })
+            .collect();
+
+        let dropped_agents = Self::dropped_agents(&rules, &response, &user_config);
+
+        Ok(RoutingExplanation {
+            response,
+            llm,
+            rules,
+            dropped_agents,
+        })
+    }
+
+    /// Run all rules with the given tags instead of asking the LLM. Rule test fixtures use
+    /// this to pin the tags a case expects, so they run without a backend.
+    pub fn route_with_tags(
+        &self,
+        input: &ClassificationInput,
+        llm_tags: &[TagResult],
+        rules_config: &RulesConfig,
+        user_config: &UserConfig,
+    ) -> InstructionsResponse {
+        let (instructions, suppressed, shadow) =
+            self.apply_all_rules_with_details(input, Some(llm_tags), rules_config, user_config);
+
+        InstructionsResponse {
+            instructions,
+            degraded: false,
+            degraded_reason: None,
+            suppressed,
+            shadow,
+        }
+    }
+
+    /// Configs for a request: per-request paths override the cached startup configs
+    fn resolve_configs(
+        &self,
+        input: &ClassificationInput,
+    ) -> Result<(Arc<UserConfig>, Arc<LlmTagConfig>, Arc<RulesConfig>)> {
+        let current = self
+            .configs
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+
+        let user_config = match input.agent_config_path {
+            Some(ref path) => {
+                info!("Loading agent config from request path: {}", path);
+                Arc::new(rules::load_user_config(path)?)
+            }
+            None => current.user_config,
         };
 
-        let tag_config_ref = if let Some(ref path) = input.llm_tags_path {
-            info!("Loading LLM tag config from request path: {}", path);
-            tag_config = rules::load_llm_tag_config(path)?;
-            &tag_config
-        } else {
-            &self.tag_config
+        let tag_config = match input.llm_tags_path {
+            Some(ref path) => {
+                info!("Loading LLM tag config from request path: {}", path);
+                Arc::new(rules::load_llm_tag_config(path)?)
+            }
+            None => current.tag_config,
         };
 
-        let rules_config_ref = if let Some(ref path) = input.rules_config_path {
-            info!("Loading rules config from request path: {}", path);
-            rules_config = rules::load_rules_config(path)?;
-            &rules_config
-        } else {
-            &self.rules_config
+        let rules_config = match input.rules_config_path {
+            Some(ref path) => {
+                info!("Loading rules config from request path: {}", path);
+                Arc::new(rules::load_rules_config(path)?)
+            }
+            None => current.rules_config,
         };
 
-        // Step 1: LLM tagging - identify semantic tags for the request
-        let llm_tags = self
-            .model_manager
-            .identify_tags(input, tag_config_ref)
-            .await?;
-        info!("LLM identified tags: {:?}", llm_tags);
+        // The startup configs were checked in `initialize`; recheck any override mix
+        let overridden = [
+            &input.agent_config_path,
+            &input.llm_tags_path,
+            &input.rules_config_path,
+        ]
+        .iter()
+        .any(|path| path.is_some());
+        if overridden {
+            validation::check_configs(
+                &user_config,
+                &tag_config,
+                &rules_config,
+                self.strict_config,
+            )?;
+        }
 
-        // Step 2: Run ALL rules (file patterns, regex, branch patterns, AND tag-based)
-        let instructions =
-            self.apply_all_rules_with_details(input, &llm_tags, rules_config_ref, user_config_ref);
+        Ok((user_config, tag_config, rules_config))
+    }
 
-        info!("Rules matched {} agents", instructions.len());
+    /// Pre-evaluate rules without tags and only ask the LLM about tags that could still
+    /// change some rule's outcome
+    async fn tag_for_rules(
+        &self,
+        input: &ClassificationInput,
+        tag_config: &LlmTagConfig,
+        rules_config: &RulesConfig,
+    ) -> Result<LlmTrace> {
+        let pending_tags = rules::pending_llm_tags(input, rules_config);
+        let relevant_tag_config = Self::relevant_tag_config(tag_config, &pending_tags);
 
-        // Step 3: Return results (no fallback - empty is valid)
-        Ok(InstructionsResponse { instructions })
+        if relevant_tag_config.tags.is_empty() {
+            info!("No rule outcome depends on LLM tags, skipping tagging");
+            return Ok(LlmTrace::default());
+        }
+
+        info!("Tagging for pending tags: {:?}", pending_tags);
+        let llm = self
+            .identify_tags_for_mode(input, &relevant_tag_config)
+            .await?;
+        info!("LLM identified tags: {:?}", llm.tags);
+        Ok(llm)
+    }
+
+    /// Restrict the tag config to the tags some rule is still waiting on
+    fn relevant_tag_config(tag_config: &LlmTagConfig, pending_tags: &[String]) -> LlmTagConfig {
+        LlmTagConfig {
+            tags: tag_config
+                .tags
+                .iter()
+                .filter(|t| pending_tags.contains(&t.name))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Run LLM tagging as the routing mode allows. The trace carries a degraded reason
+    /// when routing had to continue without tags.
+    async fn identify_tags_for_mode(
+        &self,
+        input: &ClassificationInput,
+        tag_config: &LlmTagConfig,
+    ) -> Result<LlmTrace> {
+        let requested_tags: Vec<String> = tag_config.tags.iter().map(|t| t.name.clone()).collect();
+
+        Ok(match self.routing_mode {
+            RoutingMode::Hybrid => {
+                self.model_manager.ensure_ready().await?;
+                let (tags, raw_responses) = self
+                    .model_manager
+                    .identify_tags_with_raw(input, tag_config)
+                    .await?;
+                LlmTrace {
+                    requested_tags,
+                    raw_responses,
+                    tags,
+                    degraded_reason: None,
+                }
+            }
+            RoutingMode::RulesOnly => LlmTrace {
+                degraded_reason: Some(
+                    "Rules-only routing mode: llm_tag conditions were not evaluated".to_string(),
+                ),
+                ..LlmTrace::default()
+            },
+            RoutingMode::Auto => match self
+                .model_manager
+                .identify_tags_with_raw(input, tag_config)
+                .await
+            {
+                Ok((tags, raw_responses)) => LlmTrace {
+                    requested_tags,
+                    raw_responses,
+                    tags,
+                    degraded_reason: None,
+                },
+                Err(e) => {
+                    warn!("LLM tagging failed, degrading to rules only: {:#}", e);
+                    LlmTrace {
+                        requested_tags,
+                        degraded_reason: Some(format!(
+                            "{} unavailable ({}): llm_tag conditions were not evaluated",
+                            self.model_manager.backend_name(),
+                            e
+                        )),
+                        ..LlmTrace::default()
+                    }
+                }
+            },
+        })
     }
 
     /// Apply ALL rules in a single pass (file patterns, regex, branch patterns, AND tag-based)
     /// This evaluates every rule with the LLM-identified tags available for tag conditions.
+    /// An agent selected by several rules gets a single instruction carrying every trigger,
+    /// the union of the rules' files and the highest confidence.
+    /// Suppression rules run afterwards and remove the agents they name; each removal is
+    /// returned alongside the remaining instructions.
+    /// `llm_tags` is `None` when routing is degraded, and rules that depend on tags then
+    /// do not match.
+    /// Shadow rules are evaluated last, against the final routing, and only returned as
+    /// matches with the agents they would have added or removed. Disabled rules are skipped.
     fn apply_all_rules_with_details(
         &self,
         input: &ClassificationInput,
-        llm_tags: &[String],
+        llm_tags: Option<&[TagResult]>,
         rules_config: &RulesConfig,
         user_config: &UserConfig,
-    ) -> Vec<Instruction> {
+    ) -> (Vec<Instruction>, Vec<Suppression>, Vec<ShadowMatch>) {
         let mut instructions = Vec::new();
 
         // Get files for routing - ONLY from associated_files
         let files_for_routing: Vec<String> = input.associated_files.clone().unwrap_or_default();
 
-        for rule in &rules_config.rules {
+        for (idx, rule) in rules_config.rules.iter().enumerate() {
+            if !rule.is_active() || rule.route_to_subagents.is_empty() {
+                continue;
+            }
             // Evaluate rule with LLM tags available for tag conditions
-            if let Some(match_info) =
+            let Some(match_info) =
                 self.evaluate_rule_with_details(&rule.conditions, input, llm_tags)
-            {
-                for agent_name in &rule.route_to_subagents {
-                    // Skip if we already have an instruction for this agent
-                    if instructions
-                        .iter()
-                        .any(|i: &Instruction| i.route_to_agent.name == *agent_name)
-                    {
-                        continue;
-                    }
+            else {
+                continue;
+            };
 
-                    if let Some(agent) = user_config.agents.iter().find(|a| &a.name == agent_name) {
-                        // Find which files matched this rule (for file-based rules)
-                        let matched_files =
-                            self.find_matched_files(&rule.conditions, &files_for_routing);
-
-                        // Confidence: 100 for deterministic rules, 85 for LLM tag rules
-                        let confidence = if match_info.trigger_type == "llm_tag" {
-                            85
-                        } else {
-                            100
-                        };
-
-                        instructions.push(Instruction {
-                            trigger: Trigger {
-                                name: match_info.trigger_type.clone(),
-                                description: match_info.trigger_value.clone(),
-                            },
-                            context: InstructionContext {
-                                instructions: agent.instructions.clone(),
-                                files: matched_files,
-                                confidence,
-                                priority: agent.priority,
-                            },
-                            route_to_agent: AgentInfo {
-                                name: agent.name.clone(),
-                                description: agent.description.clone(),
-                            },
-                        });
+            // Find which files matched this rule (for file-based rules)
+            let matched_files = self.find_matched_files(&rule.conditions, &files_for_routing);
+            let confidence = match_info.confidence;
+            let triggers = match_info.into_triggers(&rule.display_name(idx), &matched_files);
+
+            for agent_name in &rule.route_to_subagents {
+                // An agent selected by several rules gets one instruction listing every trigger
+                if let Some(existing) = instructions
+                    .iter_mut()
+                    .find(|i: &&mut Instruction| i.route_to_agent.name == *agent_name)
+                {
+                    existing.triggers.extend(triggers.iter().cloned());
+                    for file in &matched_files {
+                        if !existing.context.files.contains(file) {
+                            existing.context.files.push(file.clone());
+                        }
                     }
+                    existing.context.confidence = existing.context.confidence.max(confidence);
+                    continue;
+                }
+
+                if let Some(agent) = user_config.agents.iter().find(|a| &a.name == agent_name) {
+                    instructions.push(Instruction {
+                        triggers: triggers.clone(),
+                        context: InstructionContext {
+                            instructions: agent.instructions.clone(),
+                            files: matched_files.clone(),
+                            confidence,
+                            priority: agent.priority,
+                        },
+                        route_to_agent: AgentInfo {
+                            name: agent.name.clone(),
+                            description: agent.description.clone(),
+                        },
+                    });
                 }
             }
         }
 
-        instructions
+        // Suppression runs after every additive rule, so it wins regardless of rule order
+        let mut suppressed: Vec<Suppression> = Vec::new();
+        for (idx, rule) in rules_config.rules.iter().enumerate() {
+            if !rule.is_active() || rule.suppress_subagents.is_empty() {
+                continue;
+            }
+            let Some(match_info) =
+                self.evaluate_rule_with_details(&rule.conditions, input, llm_tags)
+            else {
+                continue;
+            };
+
+            let matched_files = self.find_matched_files(&rule.conditions, &files_for_routing);
+            let triggers = match_info.into_triggers(&rule.display_name(idx), &matched_files);
+
+            for agent_name in &rule.suppress_subagents {
+                if !instructions
+                    .iter()
+                    .any(|i| i.route_to_agent.name == *agent_name)
+                {
+                    continue;
+                }
+                info!(
+                    "Agent {} suppressed by {}",
+                    agent_name,
+                    rule.display_name(idx)
+                );
+                match suppressed.iter_mut().find(|s| s.agent == *agent_name) {
+                    Some(existing) => existing.triggers.extend(triggers.iter().cloned()),
+                    None => suppressed.push(Suppression {
+                        agent: agent_name.clone(),
+                        triggers: triggers.clone(),
+                    }),
+                }
+            }
+        }
+        instructions.retain(|i| !suppressed.iter().any(|s| s.agent == i.route_to_agent.name));
+
+        let mut shadow: Vec<ShadowMatch> = Vec::new();
+        for (idx, rule) in rules_config.rules.iter().enumerate() {
+            if !rule.is_shadow() {
+                continue;
+            }
+            let Some(match_info) =
+                self.evaluate_rule_with_details(&rule.conditions, input, llm_tags)
+            else {
+                continue;
+            };
+
+            let matched_files = self.find_matched_files(&rule.conditions, &files_for_routing);
+            let routed =
+                |agent: &String| instructions.iter().any(|i| i.route_to_agent.name == *agent);
+            // Agents an active rule suppressed would be removed again
+            let would_add = rule
+                .route_to_subagents
+                .iter()
+                .filter(|agent| {
+                    !routed(agent)
+                        && !suppressed.iter().any(|s| s.agent == **agent)
+                        && user_config.agents.iter().any(|a| a.name == **agent)
+                })
+                .cloned()
+                .collect();
+            let would_remove = rule
+                .suppress_subagents
+                .iter()
+                .filter(|agent| routed(agent))
+                .cloned()
+                .collect();
+
+            info!("Shadow rule {} matched", rule.display_name(idx));
+            shadow.push(ShadowMatch {
+                rule: rule.display_name(idx),
+                triggers: match_info.into_triggers(&rule.display_name(idx), &matched_files),
+                would_add,
+                would_remove,
+            });
+        }
+
+        (instructions, suppressed, shadow)
     }
 
-    /// Evaluate a rule and return match details if it matches
+    /// Evaluate a rule and return match details if it matches.
+    /// `llm_tags` is `None` when tagging was skipped or failed.
     fn evaluate_rule_with_details(
         &self,
         conditions: &RuleConditions,
         input: &ClassificationInput,
-        llm_tags: &[String],
+        llm_tags: Option<&[TagResult]>,
     ) -> Option<RuleMatchInfo> {
+        let files = rules::get_files_for_evaluation(input);
+        match self.evaluate_in_scope(conditions, input, &files, llm_tags) {
+            Evaluation::Match(info) => Some(info),
+            Evaluation::NoMatch | Evaluation::Unknown => None,
+        }
+    }
+
+    /// Evaluate conditions against the files in scope and return match details.
+    /// Every matching condition is collected (all matching branches of an `any_of`);
+    /// confidence is the strongest matching branch for `any_of` and the weakest condition
+    /// for `all_of`. Satisfied exclusions are deterministic (100).
+    /// Without tags, `llm_tag` conditions are unknown rather than false, so negating one
+    /// never matches.
+    fn evaluate_in_scope(
+        &self,
+        conditions: &RuleConditions,
+        input: &ClassificationInput,
+        files: &[String],
+        llm_tags: Option<&[TagResult]>,
+    ) -> Evaluation {
         match conditions {
-            RuleConditions::Single(condition) => {
-                self.evaluate_condition_with_details(condition, input, llm_tags)
+            RuleConditions::Single(Condition::LlmTag(_)) if llm_tags.is_none() => {
+                Evaluation::Unknown
             }
+            RuleConditions::Single(condition) => match self.evaluate_condition_with_details(
+                condition,
+                input,
+                files,
+                llm_tags.unwrap_or_default(),
+            ) {
+                Some(info) => Evaluation::Match(info),
+                None => Evaluation::NoMatch,
+            },
             RuleConditions::AnyOf { any_of } => {
+                let mut combined: Option<RuleMatchInfo> = None;
+                let mut unknown = false;
                 for c in any_of {
-                    if let Some(info) = self.evaluate_rule_with_details(c, input, llm_tags) {
-                        return Some(info);
+                    match self.evaluate_in_scope(c, input, files, llm_tags) {
+                        Evaluation::Match(info) => match combined {
+                            Some(ref mut combined) => {
+                                combined.confidence = combined.confidence.max(info.confidence);
+                                combined.merge_conditions(info);
+                            }
+                            None => combined = Some(info),
+                        },
+                        Evaluation::NoMatch => {}
+                        Evaluation::Unknown => unknown = true,
                     }
                 }
-                None
+                match combined {
+                    Some(info) => Evaluation::Match(info),
+                    None if unknown => Evaluation::Unknown,
+                    None => Evaluation::NoMatch,
+                }
             }
             RuleConditions::AllOf { all_of } => {
-                let mut first_match = None;
-                for c in all_of {
-                    match self.evaluate_rule_with_details(c, input, llm_tags) {
-                        Some(info) => {
-                            if first_match.is_none() {
-                                first_match = Some(info);
+                // Checked per file when it excludes files (see `rules::file_scopes`); the
+                // details of every file it holds for are merged like `any_of` branches
+                let mut combined: Option<RuleMatchInfo> = None;
+                let mut unknown = false;
+                for scope in rules::file_scopes(all_of, files) {
+                    match self.evaluate_all_of(all_of, input, scope, llm_tags) {
+                        Evaluation::Match(info) => match combined {
+                            Some(ref mut combined) => {
+                                combined.confidence = combined.confidence.max(info.confidence);
+                                combined.merge_conditions(info);
                             }
-                        }
-                        None => return None, // All conditions must match
+                            None => combined = Some(info),
+                        },
+                        Evaluation::NoMatch => {}
+                        Evaluation::Unknown => unknown = true,
                     }
                 }
-                first_match
+                match combined {
+                    Some(info) => Evaluation::Match(info),
+                    None if unknown => Evaluation::Unknown,
+                    None => Evaluation::NoMatch,
+                }
+            }
+            RuleConditions::Not { not } => {
+                match self.evaluate_in_scope(not, input, files, llm_tags) {
+                    Evaluation::Match(_) => Evaluation::NoMatch,
+                    Evaluation::NoMatch => {
+                        Evaluation::Match(RuleMatchInfo::negation("not", not.as_ref()))
+                    }
+                    Evaluation::Unknown => Evaluation::Unknown,
+                }
+            }
+            RuleConditions::NoneOf { none_of } => {
+                let mut unknown = false;
+                for c in none_of {
+                    match self.evaluate_in_scope(c, input, files, llm_tags) {
+                        Evaluation::Match(_) => return Evaluation::NoMatch,
+                        Evaluation::NoMatch => {}
+                        Evaluation::Unknown => unknown = true,
+                    }
+                }
+                if unknown {
+                    Evaluation::Unknown
+                } else {
+                    Evaluation::Match(RuleMatchInfo::negation("none_of", none_of))
+                }
             }
         }
     }
 
-    /// Evaluate a single condition and return match details
+    /// Evaluate the conditions of an `all_of` against the same files
+    fn evaluate_all_of(
+        &self,
+        all_of: &[RuleConditions],
+        input: &ClassificationInput,
+        files: &[String],
+        llm_tags: Option<&[TagResult]>,
+    ) -> Evaluation {
+        let mut combined: Option<RuleMatchInfo> = None;
+        let mut unknown = false;
+        for c in all_of {
+            match self.evaluate_in_scope(c, input, files, llm_tags) {
+                Evaluation::Match(info) => match combined {
+                    Some(ref mut combined) => {
+                        combined.confidence = combined.confidence.min(info.confidence);
+                        combined.merge_conditions(info);
+                    }
+                    None => combined = Some(info),
+                },
+                // All conditions must match
+                Evaluation::NoMatch => return Evaluation::NoMatch,
+                Evaluation::Unknown => unknown = true,
+            }
+        }
+        match combined {
+            _ if unknown => Evaluation::Unknown,
+            Some(info) => Evaluation::Match(info),
+            // An empty all_of
+            None => Evaluation::NoMatch,
+        }
+    }
+
+    /// Evaluate a single condition and return match details. File conditions look only
+    /// at the files in scope.
     fn evaluate_condition_with_details(
         &self,
         condition: &Condition,
         input: &ClassificationInput,
-        llm_tags: &[String],
+        files: &[String],
+        llm_tags: &[TagResult],
     ) -> Option<RuleMatchInfo> {
         match condition {
             Condition::FilePattern(pattern) => {
-                if rules::evaluate_file_pattern(pattern, input) {
-                    Some(RuleMatchInfo {
-                        trigger_type: "file_pattern".to_string(),
-                        trigger_value: pattern.clone(),
-                    })
+                if rules::file_pattern_matches(pattern, files) {
+                    Some(RuleMatchInfo::leaf("file_pattern", pattern, 100))
                 } else {
                     None
                 }
             }
             Condition::FileRegex(pattern) => {
-                if rules::evaluate_file_regex(pattern, input) {
-                    Some(RuleMatchInfo {
-                        trigger_type: "file_regex".to_string(),
-                        trigger_value: pattern.clone(),
-                    })
+                if rules::file_regex_matches(pattern, files) {
+                    Some(RuleMatchInfo::leaf("file_regex", pattern, 100))
                 } else {
                     None
                 }
             }
             Condition::PromptRegex(pattern) => {
                 if rules::evaluate_prompt_regex(pattern, input) {
-                    Some(RuleMatchInfo {
-                        trigger_type: "prompt_regex".to_string(),
-                        trigger_value: pattern.clone(),
-                    })
+                    Some(RuleMatchInfo::leaf("prompt_regex", pattern, 100))
                 } else {
                     None
                 }
             }
             Condition::BranchRegex(pattern) => {
                 if rules::evaluate_branch_regex(pattern, input) {
-                    Some(RuleMatchInfo {
-                        trigger_type: "branch_regex".to_string(),
-                        trigger_value: pattern.clone(),
-                    })
+                    Some(RuleMatchInfo::leaf("branch_regex", pattern, 100))
                 } else {
                     None
                 }
             }
-            Condition::LlmTag(tag) => {
-                if llm_tags.contains(tag) {
-                    Some(RuleMatchInfo {
-                        trigger_type: "llm_tag".to_string(),
-                        trigger_value: tag.clone(),
-                    })
-                } else {
-                    None
-                }
+            Condition::LlmTag(tag) => llm_tags
+                .iter()
+                .find(|t| &t.tag == tag)
+                .map(|t| RuleMatchInfo::leaf("llm_tag", tag, t.confidence)),
+        }
+    }
+
+    /// Evaluate conditions into a tree recording each node's result and matched files
+    fn trace_rule(
+        &self,
+        conditions: &RuleConditions,
+        input: &ClassificationInput,
+        llm_tags: Option<&[TagResult]>,
+    ) -> ConditionTrace {
+        let files = rules::get_files_for_evaluation(input);
+        self.trace_conditions(conditions, input, llm_tags, &files)
+    }
+
+    /// Combine traces of the same conditions over different files
+    fn merge_traces(mut trace: ConditionTrace, other: ConditionTrace) -> ConditionTrace {
+        trace.result |= other.result;
+        trace.undecided = !trace.result && (trace.undecided || other.undecided);
+        for file in other.matched_files {
+            if !trace.matched_files.contains(&file) {
+                trace.matched_files.push(file);
             }
         }
+        trace.confidence = trace.confidence.max(other.confidence);
+        trace.children = trace
+            .children
+            .into_iter()
+            .zip(other.children)
+            .map(|(a, b)| Self::merge_traces(a, b))
+            .collect();
+        trace
     }
 
-    /// Find which files matched a given set of conditions
-    fn find_matched_files(&self, conditions: &RuleConditions, files: &[String]) -> Vec<String> {
-        let mut matched = Vec::new();
+    /// Evaluate conditions against the files in scope into a trace tree. Undecided nodes
+    /// count as not matched, like in routing.
+    fn trace_conditions(
+        &self,
+        conditions: &RuleConditions,
+        input: &ClassificationInput,
+        llm_tags: Option<&[TagResult]>,
+        files: &[String],
+    ) -> ConditionTrace {
+        let combinator = |kind: &str,
+                          result: bool,
+                          undecided: bool,
+                          children: Vec<ConditionTrace>| ConditionTrace {
+            kind: kind.to_string(),
+            value: None,
+            result,
+            undecided,
+            matched_files: vec![],
+            confidence: None,
+            children,
+        };
+        let any_undecided = |children: &[ConditionTrace]| children.iter().any(|c| c.undecided);
+        let trace_all = |children: &[RuleConditions]| -> Vec<ConditionTrace> {
+            children
+                .iter()
+                .map(|c| self.trace_conditions(c, input, llm_tags, files))
+                .collect()
+        };
 
-        for file in files {
-            if self.file_matches_conditions(conditions, file) {
-                matched.push(file.clone());
+        match conditions {
+            RuleConditions::Single(condition) => {
+                self.trace_condition(condition, input, llm_tags, files)
+            }
+            RuleConditions::AnyOf { any_of } => {
+                let children = trace_all(any_of);
+                let result = children.iter().any(|c| c.result);
+                let undecided = !result && any_undecided(&children);
+                combinator("any_of", result, undecided, children)
+            }
+            RuleConditions::AllOf { all_of } => {
+                // Traced per file like in routing; the per-file trees are merged, so a
+                // node holds if it holds for any file
+                let mut traces = rules::file_scopes(all_of, files).into_iter().map(|scope| {
+                    let children: Vec<ConditionTrace> = all_of
+                        .iter()
+                        .map(|c| self.trace_conditions(c, input, llm_tags, scope))
+                        .collect();
+                    let failed = children.iter().any(|c| !c.result && !c.undecided);
+                    let undecided = !failed && any_undecided(&children);
+                    // An empty all_of never matches
+                    let result = !failed && !undecided && !children.is_empty();
+                    combinator("all_of", result, undecided, children)
+                });
+                let first = traces
+                    .next()
+                    .expect("file_scopes returns at least one scope");
+                traces.fold(first, Self::merge_traces)
+            }
+            RuleConditions::Not { not } => {
+                let child = self.trace_conditions(not, input, llm_tags, files);
+                let (result, undecided) = (!child.result && !child.undecided, child.undecided);
+                combinator("not", result, undecided, vec![child])
+            }
+            RuleConditions::NoneOf { none_of } => {
+                let children = trace_all(none_of);
+                let matched = children.iter().any(|c| c.result);
+                let undecided = !matched && any_undecided(&children);
+                combinator("none_of", !matched && !undecided, undecided, children)
             }
         }
+    }
+
+    /// Trace a single leaf condition
+    fn trace_condition(
+        &self,
+        condition: &Condition,
+        input: &ClassificationInput,
+        llm_tags: Option<&[TagResult]>,
+        files: &[String],
+    ) -> ConditionTrace {
+        let match_info = self.evaluate_condition_with_details(
+            condition,
+            input,
+            files,
+            llm_tags.unwrap_or_default(),
+        );
+
+        ConditionTrace {
+            kind: condition.kind().to_string(),
+            value: Some(condition.value().to_string()),
+            result: match_info.is_some(),
+            undecided: matches!(condition, Condition::LlmTag(_)) && llm_tags.is_none(),
+            matched_files: files
+                .iter()
+                .filter(|f| self.file_matches_condition(condition, f) == Some(true))
+                .cloned()
+                .collect(),
+            confidence: match condition {
+                Condition::LlmTag(_) => match_info.map(|m| m.confidence),
+                _ => None,
+            },
+            children: vec![],
+        }
+    }
+
+    /// Explain why agents referenced by `route_to_subagents` are missing from the response
+    fn dropped_agents(
+        rules: &[RuleTrace],
+        response: &InstructionsResponse,
+        user_config: &UserConfig,
+    ) -> Vec<DroppedAgent> {
+        let mut dropped: Vec<DroppedAgent> = Vec::new();
+
+        let active_rules = || rules.iter().filter(|r| r.mode.is_active());
+        for agent in active_rules().flat_map(|r| &r.route_to_subagents) {
+            let routed = response
+                .instructions
+                .iter()
+                .any(|i| &i.route_to_agent.name == agent);
+            if routed || dropped.iter().any(|d| &d.agent == agent) {
+                continue;
+            }
+
+            let matched_rules: Vec<&str> = active_rules()
+                .filter(|r| r.matched && r.route_to_subagents.contains(agent))
+                .map(|r| r.rule.as_str())
+                .collect();
+
+            let reason = if matched_rules.is_empty() {
+                "No rule routing to this agent matched".to_string()
+            } else if !user_config.agents.iter().any(|a| &a.name == agent) {
+                format!(
+                    "Matched by {} but not defined in the agent config",
+                    matched_rules.join(", ")
+                )
+            } else {
+                // Matched and defined, so only a suppression rule can have removed it
+                let mut suppressing_rules: Vec<&str> = Vec::new();
+                let triggers = response
+                    .suppressed
+                    .iter()
+                    .filter(|s| &s.agent == agent)
+                    .flat_map(|s| &s.triggers);
+                for trigger in triggers {
+                    if !suppressing_rules.contains(&trigger.rule.as_str()) {
+                        suppressing_rules.push(&trigger.rule);
+                    }
+                }
+                format!("Suppressed by {}", suppressing_rules.join(", "))
+            };
+
+            dropped.push(DroppedAgent {
+                agent: agent.clone(),
+                reason,
+            });
+        }
+
+        dropped
+    }
+
+    /// Find which files matched a given set of conditions.
+    /// Files rejected by a `not`/`none_of` file condition are never listed.
+    fn find_matched_files(&self, conditions: &RuleConditions, files: &[String]) -> Vec<String> {
+        let verdicts: Vec<(&String, Option<bool>)> = files
+            .iter()
+            .map(|file| (file, self.file_matches_conditions(conditions, file)))
+            .collect();
+
+        let matched: Vec<String> = verdicts
+            .iter()
+            .filter(|(_, verdict)| *verdict == Some(true))
+            .map(|(file, _)| (*file).clone())
+            .collect();
 
         // If no specific files matched (e.g., for intent-based rules), return all files
+        // that the conditions don't exclude
         if matched.is_empty() {
-            return files.to_vec();
+            return verdicts
+                .into_iter()
+                .filter(|(_, verdict)| *verdict != Some(false))
+                .map(|(file, _)| file.clone())
+                .collect();
         }
 
         matched
     }
 
-    /// Check if a single file matches the given conditions
-    fn file_matches_conditions(&self, conditions: &RuleConditions, file: &str) -> bool {
+    /// Check if a single file matches the given conditions.
+    /// Returns `None` when the conditions say nothing about files.
+    fn file_matches_conditions(&self, conditions: &RuleConditions, file: &str) -> Option<bool> {
         match conditions {
             RuleConditions::Single(condition) => self.file_matches_condition(condition, file),
             RuleConditions::AnyOf { any_of } => {
-                any_of.iter().any(|c| self.file_matches_conditions(c, file))
+                let verdicts: Vec<_> = any_of
+                    .iter()
+                    .filter_map(|c| self.file_matches_conditions(c, file))
+                    .collect();
+                (!verdicts.is_empty()).then(|| verdicts.contains(&true))
             }
             RuleConditions::AllOf { all_of } => {
-                all_of.iter().all(|c| self.file_matches_conditions(c, file))
+                let verdicts: Vec<_> = all_of
+                    .iter()
+                    .filter_map(|c| self.file_matches_conditions(c, file))
+                    .collect();
+                (!verdicts.is_empty()).then(|| !verdicts.contains(&false))
+            }
+            RuleConditions::Not { not } => self.file_matches_conditions(not, file).map(|m| !m),
+            RuleConditions::NoneOf { none_of } => {
+                let verdicts: Vec<_> = none_of
+                    .iter()
+                    .filter_map(|c| self.file_matches_conditions(c, file))
+                    .collect();
+                (!verdicts.is_empty()).then(|| !verdicts.contains(&true))
             }
         }
     }
 
-    /// Check if a single file matches a single condition
-    fn file_matches_condition(&self, condition: &Condition, file: &str) -> bool {
+    /// Check if a single file matches a single condition.
+    /// Returns `None` for conditions that don't look at files.
+    fn file_matches_condition(&self, condition: &Condition, file: &str) -> Option<bool> {
         use glob::Pattern;
         use regex::Regex;
 
         match condition {
-            Condition::FilePattern(pattern) => Pattern::new(pattern)
-                .map(|p| p.matches(file))
-                .unwrap_or(false),
-            Condition::FileRegex(pattern) => Regex::new(pattern)
-                .map(|r| r.is_match(file))
-                .unwrap_or(false),
+            Condition::FilePattern(pattern) => Some(
+                Pattern::new(pattern)
+                    .map(|p| p.matches(file))
+                    .unwrap_or(false),
+            ),
+            Condition::FileRegex(pattern) => Some(
+                Regex::new(pattern)
+                    .map(|r| r.is_match(file))
+                    .unwrap_or(false),
+            ),
             // Other conditions don't match files directly
-            _ => false,
-        }
-    }
-
-    // Load configs on startup (static methods check env vars and defaults)
-    fn load_user_config_static() -> Result<UserConfig> {
-        // Priority: 1. Environment variable, 2. Default
-        if let Ok(path) = std::env::var("AGENTS_CONFIG_PATH") {
-            info!("Loading agent config from env: {}", path);
-            rules::load_user_config(&path)
-        } else {
-            info!("Using default agent configuration");
-            rules::default_user_config()
-        }
-    }
-
-    fn load_tag_config_static() -> Result<LlmTagConfig> {
-        // Priority: 1. Environment variable, 2. Default
-        if let Ok(path) = std::env::var("LLM_TAGS_CONFIG_PATH") {
-            info!("Loading LLM tag config from env: {}", path);
-            rules::load_llm_tag_config(&path)
-        } else {
-            info!("Using default LLM tag configuration");
-            rules::default_llm_tag_config()
-        }
-    }
-
-    fn load_rules_config_static() -> Result<RulesConfig> {
-        // Priority: 1. Environment variable, 2. Default
-        if let Ok(path) = std::env::var("RULES_CONFIG_PATH") {
-            info!("Loading rules config from env: {}", path);
-            rules::load_rules_config(&path)
-        } else {
-            info!("Using default rules configuration");
-            rules::default_rules_config()
+            _ => None,
         }
     }
 
@@ -502,6 +1250,7 @@ impl Classifier {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::BackendError;
 
     #[test]
     fn test_is_high_confidence_with_files() {
@@ -736,4 +1485,919 @@ mod tests {
             );
         }
     }
+
+    fn tag(name: &str, confidence: u8) -> TagResult {
+        TagResult {
+            tag: name.to_string(),
+            confidence,
+            files: vec![],
+        }
+    }
+
+    fn confidence_by_agent(instructions: &[Instruction]) -> Vec<(&str, u8)> {
+        instructions
+            .iter()
+            .map(|i| (i.route_to_agent.name.as_str(), i.context.confidence))
+            .collect()
+    }
+
+    #[test]
+    fn test_instruction_confidence_from_tag_confidence() {
+        let classifier = Classifier::new(Config::default()).unwrap();
+
+        let user_config: UserConfig = serde_json::from_str(
+            r#"{"agents": [
+                {"name": "security-auditor", "description": "Security"},
+                {"name": "perf-reviewer", "description": "Performance"},
+                {"name": "rust-reviewer", "description": "Rust"},
+                {"name": "db-reviewer", "description": "Database"}
+            ]}"#,
+        )
+        .unwrap();
+        let rules_config: RulesConfig = serde_json::from_str(
+            r#"{"rules": [
+                {"conditions": {"llm_tag": "security"}, "route_to_subagents": ["security-auditor"]},
+                {"conditions": {"any_of": [{"llm_tag": "performance"}, {"llm_tag": "security"}]},
+                 "route_to_subagents": ["perf-reviewer"]},
+                {"conditions": {"all_of": [{"file_pattern": "*.rs"}, {"llm_tag": "performance"}]},
+                 "route_to_subagents": ["rust-reviewer"]},
+                {"conditions": {"file_pattern": "*.sql"}, "route_to_subagents": ["db-reviewer"]}
+            ]}"#,
+        )
+        .unwrap();
+
+        let input = ClassificationInput {
+            task: "Speed up login".to_string(),
+            intent: "implement feature".to_string(),
+            original_prompt: None,
+            associated_files: Some(vec!["src/auth.rs".to_string(), "schema.sql".to_string()]),
+            git_context: None,
+            agent_config_path: None,
+            rules_config_path: None,
+            llm_tags_path: None,
+        };
+        let llm_tags = vec![tag("security", 90), tag("performance", 60)];
+
+        let (instructions, _, _) = classifier.apply_all_rules_with_details(
+            &input,
+            Some(&llm_tags),
+            &rules_config,
+            &user_config,
+        );
+
+        assert_eq!(
+            confidence_by_agent(&instructions),
+            vec![
+                // Leaf tag condition: the tag's confidence
+                ("security-auditor", 90),
+                // any_of: strongest matching branch
+                ("perf-reviewer", 90),
+                // all_of: weakest condition (file match is deterministic)
+                ("rust-reviewer", 60),
+                // Deterministic rule
+                ("db-reviewer", 100),
+            ]
+        );
+    }
+
+    fn create_offline_classifier(routing_mode: RoutingMode) -> Classifier {
+        // Nothing listens on port 1, so every LLM call fails fast
+        let config = Config {
+            backend: Backend::Ollama,
+            ollama_url: "http://localhost:1".to_string(),
+            routing_mode,
+            ..Config::default()
+        };
+        let mut classifier = Classifier::new(config).unwrap();
+        classifier.configs = RwLock::new(ConfigSet::new(
+            serde_json::from_str(
+                r#"{"agents": [
+                    {"name": "rust-reviewer", "description": "Rust"},
+                    {"name": "security-auditor", "description": "Security"}
+                ]}"#,
+            )
+            .unwrap(),
+            serde_json::from_str(
+                r#"{"tags": [{"name": "security", "description": "Security", "examples": []}]}"#,
+            )
+            .unwrap(),
+            serde_json::from_str(
+                r#"{"rules": [
+                    {"conditions": {"file_pattern": "*.rs"}, "route_to_subagents": ["rust-reviewer"]},
+                    {"conditions": {"llm_tag": "security"}, "route_to_subagents": ["security-auditor"]}
+                ]}"#,
+            )
+            .unwrap(),
+        ));
+        classifier
+    }
+
+    fn set_rules(classifier: &mut Classifier, rules_json: &str) {
+        classifier.configs.get_mut().unwrap().rules_config =
+            Arc::new(serde_json::from_str(rules_json).unwrap());
+    }
+
+    fn create_rust_input() -> ClassificationInput {
+        ClassificationInput {
+            task: "Harden login".to_string(),
+            intent: "implement feature".to_string(),
+            original_prompt: None,
+            associated_files: Some(vec!["src/auth.rs".to_string()]),
+            git_context: None,
+            agent_config_path: None,
+            rules_config_path: None,
+            llm_tags_path: None,
+        }
+    }
+
+    #[test]
+    fn test_every_trigger_reported() {
+        let classifier = Classifier::new(Config::default()).unwrap();
+
+        let user_config: UserConfig = serde_json::from_str(
+            r#"{"agents": [{"name": "security-auditor", "description": "Security"}]}"#,
+        )
+        .unwrap();
+        let rules_config: RulesConfig = serde_json::from_str(
+            r#"{"rules": [
+                {"description": "Auth files",
+                 "conditions": {"any_of": [{"file_pattern": "*auth*"}, {"file_pattern": "*login*"}]},
+                 "route_to_subagents": ["security-auditor"]},
+                {"conditions": {"file_pattern": "*.sql"}, "route_to_subagents": ["security-auditor"]},
+                {"conditions": {"llm_tag": "security"}, "route_to_subagents": ["security-auditor"]}
+            ]}"#,
+        )
+        .unwrap();
+
+        let input = ClassificationInput {
+            task: "Harden login".to_string(),
+            intent: "implement feature".to_string(),
+            original_prompt: None,
+            associated_files: Some(vec![
+                "src/auth.rs".to_string(),
+                "src/login.rs".to_string(),
+                "schema.sql".to_string(),
+            ]),
+            git_context: None,
+            agent_config_path: None,
+            rules_config_path: None,
+            llm_tags_path: None,
+        };
+        let llm_tags = vec![tag("security", 70)];
+
+        let (instructions, _, _) = classifier.apply_all_rules_with_details(
+            &input,
+            Some(&llm_tags),
+            &rules_config,
+            &user_config,
+        );
+        assert_eq!(instructions.len(), 1);
+        let instruction = &instructions[0];
+
+        let triggers: Vec<(&str, &str, &str)> = instruction
+            .triggers
+            .iter()
+            .map(|t| (t.rule.as_str(), t.name.as_str(), t.description.as_str()))
+            .collect();
+        assert_eq!(
+            triggers,
+            vec![
+                ("Auth files", "file_pattern", "*auth*"),
+                ("Auth files", "file_pattern", "*login*"),
+                ("Rule #2", "file_pattern", "*.sql"),
+                ("Rule #3", "llm_tag", "security"),
+            ]
+        );
+        assert_eq!(
+            instruction.triggers[0].files,
+            vec!["src/auth.rs", "src/login.rs"]
+        );
+        assert_eq!(instruction.triggers[2].files, vec!["schema.sql"]);
+
+        // Files are merged across rules; confidence is the strongest rule's
+        assert_eq!(
+            instruction.context.files,
+            vec!["src/auth.rs", "src/login.rs", "schema.sql"]
+        );
+        assert_eq!(instruction.context.confidence, 100);
+    }
+
+    #[test]
+    fn test_negated_conditions_exclude_files() {
+        let classifier = Classifier::new(Config::default()).unwrap();
+
+        let user_config: UserConfig = serde_json::from_str(
+            r#"{"agents": [
+                {"name": "rust-reviewer", "description": "Rust"},
+                {"name": "security-auditor", "description": "Security"}
+            ]}"#,
+        )
+        .unwrap();
+        let rules_config: RulesConfig = serde_json::from_str(
+            r#"{"rules": [
+                {"conditions": {"all_of": [
+                    {"not": {"file_pattern": "**/*_test.rs"}},
+                    {"file_pattern": "src/**/*.rs"}
+                 ]},
+                 "route_to_subagents": ["rust-reviewer"]},
+                {"conditions": {"any_of": [
+                    {"llm_tag": "security"},
+                    {"none_of": [{"file_pattern": "*.md"}, {"file_pattern": "**/*_test.rs"}]}
+                 ]},
+                 "route_to_subagents": ["security-auditor"]}
+            ]}"#,
+        )
+        .unwrap();
+
+        let input = ClassificationInput {
+            task: "Harden login".to_string(),
+            intent: "implement feature".to_string(),
+            original_prompt: None,
+            associated_files: Some(vec!["src/auth.rs".to_string(), "NOTES.md".to_string()]),
+            git_context: None,
+            agent_config_path: None,
+            rules_config_path: None,
+            llm_tags_path: None,
+        };
+        let llm_tags = vec![tag("security", 80)];
+
+        let (instructions, _, _) = classifier.apply_all_rules_with_details(
+            &input,
+            Some(&llm_tags),
+            &rules_config,
+            &user_config,
+        );
+        assert_eq!(instructions.len(), 2);
+
+        // The positive condition is reported as the trigger, not the exclusion
+        let rust = &instructions[0];
+        assert_eq!(rust.triggers[0].name, "file_pattern");
+        assert_eq!(rust.triggers[0].description, "src/**/*.rs");
+        assert_eq!(rust.context.files, vec!["src/auth.rs"]);
+
+        // NOTES.md is excluded, so only the tag matches; the excluded file is left out
+        let security = &instructions[1];
+        assert_eq!(security.triggers[0].name, "llm_tag");
+        assert_eq!(security.context.confidence, 80);
+        assert_eq!(security.context.files, vec!["src/auth.rs"]);
+
+        // Next to a positive file condition, exclusions apply per file: a test file next
+        // to a source file does not stop the rule, it is only left out of the files.
+        // A none_of on its own still means "no file matches".
+        let mut mixed = ClassificationInput {
+            task: input.task.clone(),
+            intent: input.intent.clone(),
+            original_prompt: None,
+            associated_files: Some(vec![
+                "src/auth.rs".to_string(),
+                "src/auth_test.rs".to_string(),
+            ]),
+            git_context: None,
+            agent_config_path: None,
+            rules_config_path: None,
+            llm_tags_path: None,
+        };
+        let (instructions, _, _) =
+            classifier.apply_all_rules_with_details(&mixed, Some(&[]), &rules_config, &user_config);
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(instructions[0].route_to_agent.name, "rust-reviewer");
+        assert_eq!(instructions[0].context.files, vec!["src/auth.rs"]);
+
+        // ... but a request with only excluded files does not match
+        mixed.associated_files = Some(vec!["src/auth_test.rs".to_string()]);
+        let (instructions, _, _) =
+            classifier.apply_all_rules_with_details(&mixed, Some(&[]), &rules_config, &user_config);
+        assert!(instructions.is_empty());
+
+        // A rule made only of an exclusion triggers on it
+        let rules_config: RulesConfig = serde_json::from_str(
+            r#"{"rules": [{"conditions": {"not": {"branch_regex": "^release/"}},
+                           "route_to_subagents": ["rust-reviewer"]}]}"#,
+        )
+        .unwrap();
+        let (instructions, _, _) =
+            classifier.apply_all_rules_with_details(&input, Some(&[]), &rules_config, &user_config);
+        assert_eq!(instructions[0].triggers[0].name, "not");
+        assert_eq!(
+            instructions[0].triggers[0].description,
+            r#"{"branch_regex":"^release/"}"#
+        );
+        assert_eq!(instructions[0].context.confidence, 100);
+        assert_eq!(instructions[0].context.files.len(), 2);
+    }
+
+    #[test]
+    fn test_all_of_file_conditions_match_across_files() {
+        let classifier = Classifier::new(Config::default()).unwrap();
+        let user_config: UserConfig = serde_json::from_str(
+            r#"{"agents": [{"name": "security-auditor", "description": "Security"}]}"#,
+        )
+        .unwrap();
+        let rules_config: RulesConfig = serde_json::from_str(
+            r#"{"rules": [{"conditions": {"all_of": [{"file_pattern": "*.ts"}, {"file_pattern": "*auth*"}]},
+                           "route_to_subagents": ["security-auditor"]}]}"#,
+        )
+        .unwrap();
+        let input = ClassificationInput {
+            task: "Fix login".to_string(),
+            intent: "implement feature".to_string(),
+            original_prompt: None,
+            associated_files: Some(vec!["src/a.ts".to_string(), "src/auth.py".to_string()]),
+            git_context: None,
+            agent_config_path: None,
+            rules_config_path: None,
+            llm_tags_path: None,
+        };
+
+        // Each file condition holds for some file; no single file has to satisfy both
+        let (instructions, _, _) =
+            classifier.apply_all_rules_with_details(&input, None, &rules_config, &user_config);
+        assert_eq!(instructions.len(), 1);
+        assert!(
+            classifier
+                .trace_rule(&rules_config.rules[0].conditions, &input, None)
+                .result
+        );
+    }
+
+    #[test]
+    fn test_suppression_rules_remove_agents() {
+        let classifier = Classifier::new(Config::default()).unwrap();
+
+        let user_config: UserConfig = serde_json::from_str(
+            r#"{"agents": [
+                {"name": "js-reviewer", "description": "JavaScript"},
+                {"name": "security-auditor", "description": "Security"}
+            ]}"#,
+        )
+        .unwrap();
+        // Suppression rules come first to show that order does not matter
+        let rules_config: RulesConfig = serde_json::from_str(
+            r#"{"rules": [
+                {"description": "Third-party vendored code",
+                 "conditions": {"all_of": [
+                    {"file_pattern": "vendor/**"},
+                    {"not": {"file_pattern": "vendor/ours/**"}}
+                 ]},
+                 "suppress_subagents": ["js-reviewer"]},
+                {"conditions": {"branch_regex": "^docs/"},
+                 "suppress_subagents": ["security-auditor", "perf-reviewer"]},
+                {"conditions": {"file_pattern": "*.js"}, "route_to_subagents": ["js-reviewer"]},
+                {"conditions": {"llm_tag": "security"}, "route_to_subagents": ["security-auditor"]}
+            ]}"#,
+        )
+        .unwrap();
+
+        let input = |files: Vec<&str>| ClassificationInput {
+            task: "Update login".to_string(),
+            intent: "implement feature".to_string(),
+            original_prompt: None,
+            associated_files: Some(files.into_iter().map(String::from).collect()),
+            git_context: None,
+            agent_config_path: None,
+            rules_config_path: None,
+            llm_tags_path: None,
+        };
+        let llm_tags = vec![tag("security", 90)];
+
+        let (instructions, suppressed, _) = classifier.apply_all_rules_with_details(
+            &input(vec!["vendor/jquery.js"]),
+            Some(&llm_tags),
+            &rules_config,
+            &user_config,
+        );
+        assert_eq!(
+            confidence_by_agent(&instructions),
+            vec![("security-auditor", 90)]
+        );
+        assert_eq!(suppressed.len(), 1);
+        assert_eq!(suppressed[0].agent, "js-reviewer");
+        assert_eq!(suppressed[0].triggers[0].rule, "Third-party vendored code");
+        assert_eq!(suppressed[0].triggers[0].description, "vendor/**");
+
+        // No file is third-party vendored code, so nothing is suppressed
+        let (instructions, suppressed, _) = classifier.apply_all_rules_with_details(
+            &input(vec!["vendor/ours/app.js", "src/app.js"]),
+            Some(&llm_tags),
+            &rules_config,
+            &user_config,
+        );
+        assert_eq!(instructions.len(), 2);
+        assert!(suppressed.is_empty());
+    }
+
+    #[test]
+    fn test_shadow_rules_are_reported_separately() {
+        let classifier = Classifier::new(Config::default()).unwrap();
+
+        let user_config: UserConfig = serde_json::from_str(
+            r#"{"agents": [
+                {"name": "js-reviewer", "description": "JavaScript"},
+                {"name": "security-auditor", "description": "Security"},
+                {"name": "perf-reviewer", "description": "Performance"}
+            ]}"#,
+        )
+        .unwrap();
+        let rules_config: RulesConfig = serde_json::from_str(
+            r#"{"rules": [
+                {"id": "js", "conditions": {"file_pattern": "*.js"}, "route_to_subagents": ["js-reviewer"]},
+                {"id": "security", "conditions": {"llm_tag": "security"},
+                 "route_to_subagents": ["security-auditor"], "enabled": false},
+                {"id": "perf", "mode": "shadow", "conditions": {"file_pattern": "src/**"},
+                 "route_to_subagents": ["js-reviewer", "perf-reviewer"]},
+                {"id": "vendor", "mode": "shadow", "conditions": {"file_pattern": "vendor/**"},
+                 "suppress_subagents": ["js-reviewer"]},
+                {"id": "docs", "mode": "shadow", "conditions": {"file_pattern": "*.md"},
+                 "route_to_subagents": ["perf-reviewer"]}
+            ]}"#,
+        )
+        .unwrap();
+        let input = ClassificationInput {
+            task: "Update login".to_string(),
+            intent: "implement feature".to_string(),
+            original_prompt: None,
+            associated_files: Some(vec![
+                "src/app.js".to_string(),
+                "vendor/jquery.js".to_string(),
+            ]),
+            git_context: None,
+            agent_config_path: None,
+            rules_config_path: None,
+            llm_tags_path: None,
+        };
+
+        let (instructions, suppressed, shadow) = classifier.apply_all_rules_with_details(
+            &input,
+            Some(&[tag("security", 90)]),
+            &rules_config,
+            &user_config,
+        );
+        // Only the active rule routes; the disabled one is skipped despite its tag
+        assert_eq!(
+            confidence_by_agent(&instructions),
+            vec![("js-reviewer", 100)]
+        );
+        assert!(suppressed.is_empty());
+
+        let shadow: Vec<(&str, &[String], &[String])> = shadow
+            .iter()
+            .map(|s| (s.rule.as_str(), &s.would_add[..], &s.would_remove[..]))
+            .collect();
+        assert_eq!(
+            shadow,
+            vec![
+                ("perf", &["perf-reviewer".to_string()][..], &[][..]),
+                ("vendor", &[][..], &["js-reviewer".to_string()][..]),
+            ]
+        );
+        assert!(rules::pending_llm_tags(&input, &rules_config).is_empty());
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_initialize_without_llm() {
+        // Every mode loads configs even when the LLM is unreachable
+        for mode in [
+            RoutingMode::Hybrid,
+            RoutingMode::RulesOnly,
+            RoutingMode::Auto,
+        ] {
+            let mut classifier = create_offline_classifier(mode);
+            assert!(classifier.initialize().await.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rules_only_mode_skips_llm() {
+        let classifier = create_offline_classifier(RoutingMode::RulesOnly);
+
+        let response = classifier
+            .classify_enhanced(&create_rust_input())
+            .await
+            .unwrap();
+
+        let agents: Vec<&str> = response
+            .instructions
+            .iter()
+            .map(|i| i.route_to_agent.name.as_str())
+            .collect();
+        assert_eq!(agents, vec!["rust-reviewer"]);
+        assert!(response.degraded);
+        assert!(response
+            .degraded_reason
+            .unwrap()
+            .contains("Rules-only routing mode"));
+    }
+
+    #[tokio::test]
+    async fn test_auto_mode_degrades_when_llm_unavailable() {
+        let classifier = create_offline_classifier(RoutingMode::Auto);
+
+        let response = classifier
+            .classify_enhanced(&create_rust_input())
+            .await
+            .unwrap();
+
+        assert_eq!(response.instructions.len(), 1);
+        assert_eq!(
+            response.instructions[0].route_to_agent.name,
+            "rust-reviewer"
+        );
+        assert!(response.degraded);
+        assert!(response
+            .degraded_reason
+            .unwrap()
+            .starts_with("Ollama unavailable"));
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_mode_fails_when_llm_unavailable() {
+        let classifier = create_offline_classifier(RoutingMode::Hybrid);
+
+        let err = classifier
+            .classify_enhanced(&create_rust_input())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BackendError>(),
+            Some(BackendError::NotReady(message)) if message.starts_with("Ollama is not running")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_llm_skipped_when_no_tag_can_change_outcome() {
+        // Hybrid mode with an unreachable LLM: succeeds only if tagging is skipped
+        let mut classifier = create_offline_classifier(RoutingMode::Hybrid);
+        set_rules(
+            &mut classifier,
+            r#"{"rules": [
+                {"conditions": {"file_pattern": "*.rs"}, "route_to_subagents": ["rust-reviewer"]},
+                {"conditions": {"all_of": [{"file_pattern": "*.ts"}, {"llm_tag": "security"}]},
+                 "route_to_subagents": ["security-auditor"]}
+            ]}"#,
+        );
+
+        let response = classifier
+            .classify_enhanced(&create_rust_input())
+            .await
+            .unwrap();
+
+        assert_eq!(response.instructions.len(), 1);
+        assert_eq!(
+            response.instructions[0].route_to_agent.name,
+            "rust-reviewer"
+        );
+        assert!(!response.degraded);
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_hybrid_mode_routes_from_rules_without_backend() {
+        use std::fs;
+
+        // Nothing needs LLM tags, so neither initialize nor routing touches the backend
+        let dir = std::env::temp_dir().join(format!(
+            "agent-router-hybrid-offline-test-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("agents.json"),
+            r#"{"agents": [
+                {"name": "rust-reviewer", "description": "Rust"},
+                {"name": "security-auditor", "description": "Security"}
+            ]}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("llm-tags.json"),
+            r#"{"tags": [{"name": "security", "description": "Security", "examples": []}]}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("rules.json"),
+            r#"{"rules": [
+                {"conditions": {"file_pattern": "*.rs"}, "route_to_subagents": ["rust-reviewer"]},
+                {"conditions": {"all_of": [{"file_pattern": "*.ts"}, {"llm_tag": "security"}]},
+                 "route_to_subagents": ["security-auditor"]}
+            ]}"#,
+        )
+        .unwrap();
+        std::env::set_var("AGENTS_CONFIG_PATH", dir.join("agents.json"));
+        std::env::set_var("LLM_TAGS_CONFIG_PATH", dir.join("llm-tags.json"));
+        std::env::set_var("RULES_CONFIG_PATH", dir.join("rules.json"));
+
+        let mut classifier = create_offline_classifier(RoutingMode::Hybrid);
+        let result = classifier.initialize().await;
+        let response = match result {
+            Ok(()) => classifier.classify_enhanced(&create_rust_input()).await,
+            Err(e) => Err(e),
+        };
+
+        std::env::remove_var("AGENTS_CONFIG_PATH");
+        std::env::remove_var("LLM_TAGS_CONFIG_PATH");
+        std::env::remove_var("RULES_CONFIG_PATH");
+        let _ = fs::remove_dir_all(&dir);
+
+        let response = response.unwrap();
+        assert_eq!(response.instructions.len(), 1);
+        assert_eq!(
+            response.instructions[0].route_to_agent.name,
+            "rust-reviewer"
+        );
+        assert!(!response.degraded);
+    }
+
+    #[tokio::test]
+    async fn test_degraded_routing_does_not_match_negated_tags() {
+        let mut classifier = create_offline_classifier(RoutingMode::Auto);
+        set_rules(
+            &mut classifier,
+            r#"{"rules": [
+                {"conditions": {"all_of": [{"file_pattern": "*.rs"}, {"not": {"llm_tag": "security"}}]},
+                 "route_to_subagents": ["rust-reviewer"]},
+                {"conditions": {"none_of": [{"llm_tag": "security"}, {"file_pattern": "*.md"}]},
+                 "route_to_subagents": ["security-auditor"]},
+                {"conditions": {"any_of": [{"file_pattern": "*.rs"}, {"not": {"llm_tag": "security"}}]},
+                 "suppress_subagents": ["security-auditor"]}
+            ]}"#,
+        );
+
+        // The LLM is unreachable, so whether the request is about security is unknown
+        let response = classifier
+            .classify_enhanced(&create_rust_input())
+            .await
+            .unwrap();
+        assert!(response.degraded);
+        assert!(response.instructions.is_empty());
+        assert!(response.suppressed.is_empty());
+
+        // With tags known, the same negations match
+        let (instructions, _, _) = classifier.apply_all_rules_with_details(
+            &create_rust_input(),
+            Some(&[]),
+            &classifier.configs.read().unwrap().rules_config,
+            &classifier.configs.read().unwrap().user_config,
+        );
+        assert_eq!(
+            confidence_by_agent(&instructions),
+            vec![("rust-reviewer", 100)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_explain_routing_agrees_with_routing_on_empty_all_of() {
+        let mut classifier = create_offline_classifier(RoutingMode::RulesOnly);
+        set_rules(
+            &mut classifier,
+            r#"{"rules": [{"conditions": {"all_of": []}, "route_to_subagents": ["rust-reviewer"]}]}"#,
+        );
+
+        let response = classifier
+            .classify_enhanced(&create_rust_input())
+            .await
+            .unwrap();
+        assert!(response.instructions.is_empty());
+
+        let explanation = classifier
+            .explain_routing(&create_rust_input())
+            .await
+            .unwrap();
+        assert!(!explanation.rules[0].matched);
+        assert!(!explanation.rules[0].conditions.undecided);
+    }
+
+    #[tokio::test]
+    async fn test_explain_routing_traces_rules_and_dropped_agents() {
+        let mut classifier = create_offline_classifier(RoutingMode::RulesOnly);
+        set_rules(
+            &mut classifier,
+            r#"{"rules": [
+                {"description": "Rust files",
+                 "conditions": {"any_of": [{"file_pattern": "*.rs"}, {"not": {"llm_tag": "docs"}}]},
+                 "route_to_subagents": ["rust-reviewer"]},
+                {"conditions": {"llm_tag": "security"}, "route_to_subagents": ["security-auditor"]},
+                {"conditions": {"file_regex": "auth"}, "route_to_subagents": ["auth-expert"]},
+                {"description": "No reviews on main",
+                 "conditions": {"branch_regex": ".*"},
+                 "suppress_subagents": ["rust-reviewer"]}
+            ]}"#,
+        );
+
+        let mut input = create_rust_input();
+        input.git_context = Some(GitContext {
+            branch: "main".to_string(),
+            changed_files: vec![],
+            staged_files: vec![],
+            tag: None,
+        });
+        let explanation = classifier.explain_routing(&input).await.unwrap();
+
+        // Same outcome as get_instructions
+        assert!(explanation.response.instructions.is_empty());
+        assert!(explanation.response.degraded);
+        assert!(explanation.llm.requested_tags.is_empty());
+        assert!(explanation.llm.degraded_reason.is_some());
+
+        assert_eq!(explanation.rules.len(), 4);
+        let rust = &explanation.rules[0];
+        assert_eq!(rust.rule, "Rust files");
+        assert!(rust.matched);
+        assert_eq!(rust.conditions.kind, "any_of");
+        let file_leaf = &rust.conditions.children[0];
+        assert_eq!(file_leaf.value.as_deref(), Some("*.rs"));
+        assert!(file_leaf.result);
+        assert_eq!(file_leaf.matched_files, vec!["src/auth.rs"]);
+        // Without tags a negated tag is undecided, not proof of absence
+        let not = &rust.conditions.children[1];
+        assert_eq!(not.kind, "not");
+        assert!(!not.result);
+        assert!(not.undecided);
+        assert!(not.children[0].undecided);
+
+        assert!(!explanation.rules[1].matched);
+        assert_eq!(explanation.rules[1].rule, "Rule #2");
+
+        let reasons: Vec<(&str, &str)> = explanation
+            .dropped_agents
+            .iter()
+            .map(|d| (d.agent.as_str(), d.reason.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                ("rust-reviewer", "Suppressed by No reviews on main"),
+                ("security-auditor", "No rule routing to this agent matched"),
+                (
+                    "auth-expert",
+                    "Matched by Rule #3 but not defined in the agent config"
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_strict_config_rejects_override_with_unknown_agent() {
+        use std::fs;
+
+        let temp_path = std::env::temp_dir().join(format!(
+            "test_strict_unknown_agent_rules-{}.json",
+            std::process::id()
+        ));
+        fs::write(
+            &temp_path,
+            r#"{"rules": [{"conditions": {"file_pattern": "*.rs"}, "route_to_subagents": ["ghost"]}]}"#,
+        )
+        .unwrap();
+
+        let mut input = create_rust_input();
+        input.rules_config_path = Some(temp_path.to_str().unwrap().to_string());
+
+        let mut classifier = create_offline_classifier(RoutingMode::RulesOnly);
+        let response = classifier.classify_enhanced(&input).await.unwrap();
+        assert!(response.instructions.is_empty());
+
+        classifier.strict_config = true;
+        let err = classifier.classify_enhanced(&input).await.unwrap_err();
+        assert!(err.to_string().contains("'ghost'"));
+
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_reload_configs_swaps_in_valid_configs_only() {
+        use std::fs;
+
+        let dir =
+            std::env::temp_dir().join(format!("agent-router-reload-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let rules_path = dir.join("rules.json");
+        let write_rules = |agent: &str| {
+            let rules = format!(
+                r#"{{"rules": [{{"description": "Rust", "conditions": {{"file_pattern": "*.rs"}}, "route_to_subagents": ["{}"]}}]}}"#,
+                agent
+            );
+            fs::write(&rules_path, rules).unwrap();
+        };
+        fs::write(
+            dir.join("agents.json"),
+            r#"{"agents": [
+                {"name": "rust-reviewer", "description": "Rust"},
+                {"name": "security-auditor", "description": "Security"}
+            ]}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("llm-tags.json"),
+            r#"{"tags": [{"name": "security", "description": "Security", "examples": []}]}"#,
+        )
+        .unwrap();
+        write_rules("rust-reviewer");
+        std::env::set_var("AGENTS_CONFIG_PATH", dir.join("agents.json"));
+        std::env::set_var("LLM_TAGS_CONFIG_PATH", dir.join("llm-tags.json"));
+        std::env::set_var("RULES_CONFIG_PATH", &rules_path);
+
+        let mut classifier = Classifier::new(Config {
+            routing_mode: RoutingMode::RulesOnly,
+            ..Config::default()
+        })
+        .unwrap();
+        classifier.initialize().await.unwrap();
+        let routed_agent = |classifier: &Classifier| {
+            let (user_config, _, rules_config) =
+                classifier.resolve_configs(&create_rust_input()).unwrap();
+            classifier
+                .route_with_tags(&create_rust_input(), &[], &rules_config, &user_config)
+                .instructions[0]
+                .route_to_agent
+                .name
+                .clone()
+        };
+        assert_eq!(routed_agent(&classifier), "rust-reviewer");
+
+        write_rules("security-auditor");
+        let diff = classifier.reload_configs().unwrap();
+        assert_eq!(diff.rules.changed, vec!["Rust"]);
+        assert!(diff.agents.is_empty());
+        assert_eq!(routed_agent(&classifier), "security-auditor");
+
+        // Invalid files are rejected and the previous configs stay in use
+        fs::write(
+            &rules_path,
+            r#"{"rules": [{"conditions": {"file_pattern": "*.rs"}}]}"#,
+        )
+        .unwrap();
+        assert!(classifier.reload_configs().is_err());
+        assert_eq!(routed_agent(&classifier), "security-auditor");
+
+        std::env::remove_var("AGENTS_CONFIG_PATH");
+        std::env::remove_var("LLM_TAGS_CONFIG_PATH");
+        std::env::remove_var("RULES_CONFIG_PATH");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_set_search_roots_keeps_roots_on_error() {
+        use std::fs;
+
+        let dir = std::env::temp_dir().join(format!(
+            "agent-router-search-roots-test-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        for (repo, rules) in [
+            (
+                "good",
+                r#"{"rules": [{"id": "rust", "conditions": {"file_pattern": "*.rs"}, "route_to_subagents": ["language-reviewer-rust"]}]}"#,
+            ),
+            ("bad", r#"{"rules": [}"#),
+        ] {
+            fs::create_dir_all(dir.join(repo).join(".git")).unwrap();
+            fs::create_dir_all(dir.join(repo).join(".agent-router")).unwrap();
+            fs::write(dir.join(repo).join(".agent-router/rules.json"), rules).unwrap();
+        }
+        std::env::set_var("XDG_CONFIG_HOME", dir.join("no-user-config"));
+
+        let classifier = create_offline_classifier(RoutingMode::RulesOnly);
+        classifier.set_search_roots(vec![dir.join("good")]).unwrap();
+        assert!(classifier.set_search_roots(vec![dir.join("bad")]).is_err());
+
+        // The failed call leaves the roots alone, so later reloads still find the good repo
+        assert_eq!(classifier.search_roots(), vec![dir.join("good")]);
+        classifier.reload_configs().unwrap();
+        assert_eq!(
+            classifier.config_sources().rules.path(),
+            Some(dir.join("good/.agent-router/rules.json").as_path())
+        );
+
+        std::env::remove_var("XDG_CONFIG_HOME");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_relevant_tag_config_keeps_pending_tags() {
+        let tag_config: LlmTagConfig = serde_json::from_str(
+            r#"{"tags": [
+                {"name": "security", "description": "Security", "examples": []},
+                {"name": "performance", "description": "Performance", "examples": []},
+                {"name": "docs", "description": "Docs", "examples": []}
+            ]}"#,
+        )
+        .unwrap();
+
+        let relevant = Classifier::relevant_tag_config(
+            &tag_config,
+            &[
+                "docs".to_string(),
+                "security".to_string(),
+                "undefined".to_string(),
+            ],
+        );
+
+        let names: Vec<&str> = relevant.tags.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["security", "docs"]);
+    }
 }
@@ -0,0 +1,367 @@
+// Locating the startup configs.
+//
+// Each of the agents, rules and llm-tags configs is resolved on its own, and the first
+// candidate that exists wins. Within a directory `.json` is preferred over `.yaml`, `.yml`
+// and `.toml`.
+//
+// 1. the `*_CONFIG_PATH` env var (used even if the file is missing, so a typo is an error)
+// 2. `.agent-router/<name>.<ext>` in a search root or one of its parents, up to the git worktree
+//    root. The search roots are the MCP roots supplied by the client, else the working
+//    directory.
+// 3. `<name>.<ext>` in the user config dir, `$XDG_CONFIG_HOME/agent-router` or
+//    `~/.config/agent-router`
+// 4. `./config/<name>.<ext>` relative to the working directory, where earlier versions looked
+// 5. the defaults compiled into the binary
+use crate::format::ConfigFormat;
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Directory holding a repository's configs
+pub const REPOSITORY_CONFIG_DIR: &str = ".agent-router";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigKind {
+    Agents,
+    LlmTags,
+    Rules,
+}
+
+impl ConfigKind {
+    pub const ALL: [ConfigKind; 3] = [ConfigKind::Agents, ConfigKind::LlmTags, ConfigKind::Rules];
+
+    /// Name used on the command line and in tool arguments
+    pub fn cli_name(self) -> &'static str {
+        match self {
+            ConfigKind::Agents => "agents",
+            ConfigKind::LlmTags => "tags",
+            ConfigKind::Rules => "rules",
+        }
+    }
+
+    /// Parse a `cli_name` (or the file stem `llm-tags`)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "agents" => Some(ConfigKind::Agents),
+            "tags" | "llm-tags" => Some(ConfigKind::LlmTags),
+            "rules" => Some(ConfigKind::Rules),
+            _ => None,
+        }
+    }
+
+    /// File name without the extension
+    pub fn file_stem(self) -> &'static str {
+        match self {
+            ConfigKind::Agents => "agents",
+            ConfigKind::LlmTags => "llm-tags",
+            ConfigKind::Rules => "rules",
+        }
+    }
+
+    /// The file names this config is looked for under, in order of preference
+    fn file_names(self) -> impl Iterator<Item = String> {
+        ConfigFormat::EXTENSIONS
+            .iter()
+            .map(move |ext| format!("{}.{}", self.file_stem(), ext))
+    }
+
+    pub fn env_var(self) -> &'static str {
+        match self {
+            ConfigKind::Agents => "AGENTS_CONFIG_PATH",
+            ConfigKind::LlmTags => "LLM_TAGS_CONFIG_PATH",
+            ConfigKind::Rules => "RULES_CONFIG_PATH",
+        }
+    }
+}
+
+/// Where a config was loaded from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "source", content = "path", rename_all = "snake_case")]
+pub enum ConfigSource {
+    /// Named by a CLI flag
+    CommandLine(PathBuf),
+    /// Named by the `*_CONFIG_PATH` env var
+    Env(PathBuf),
+    /// `.agent-router/` in the repository
+    Repository(PathBuf),
+    /// The user config dir
+    User(PathBuf),
+    /// `./config/` in the working directory
+    WorkingDir(PathBuf),
+    /// Compiled into the binary
+    Bundled,
+}
+
+impl ConfigSource {
+    /// The file to load, `None` for the bundled defaults
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            ConfigSource::CommandLine(path)
+            | ConfigSource::Env(path)
+            | ConfigSource::Repository(path)
+            | ConfigSource::User(path)
+            | ConfigSource::WorkingDir(path) => Some(path),
+            ConfigSource::Bundled => None,
+        }
+    }
+
+    /// Whether discovery stops at this candidate. An env var path is always used.
+    fn exists(&self) -> bool {
+        match self {
+            ConfigSource::CommandLine(_) | ConfigSource::Env(_) | ConfigSource::Bundled => true,
+            other => other.path().is_some_and(Path::is_file),
+        }
+    }
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::CommandLine(path) => write!(f, "{} (command line)", path.display()),
+            ConfigSource::Env(path) => write!(f, "{} (env)", path.display()),
+            ConfigSource::Repository(path) => write!(f, "{} (repository)", path.display()),
+            ConfigSource::User(path) => write!(f, "{} (user)", path.display()),
+            ConfigSource::WorkingDir(path) => {
+                write!(f, "{} (working directory)", path.display())
+            }
+            ConfigSource::Bundled => write!(f, "bundled defaults"),
+        }
+    }
+}
+
+/// The source of each startup config
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigSources {
+    pub agents: ConfigSource,
+    pub llm_tags: ConfigSource,
+    pub rules: ConfigSource,
+}
+
+impl Default for ConfigSources {
+    fn default() -> Self {
+        Self {
+            agents: ConfigSource::Bundled,
+            llm_tags: ConfigSource::Bundled,
+            rules: ConfigSource::Bundled,
+        }
+    }
+}
+
+impl ConfigSources {
+    /// Discover every config from the given roots
+    pub fn discover(search_roots: &[PathBuf]) -> Self {
+        Self {
+            agents: discover(ConfigKind::Agents, search_roots),
+            llm_tags: discover(ConfigKind::LlmTags, search_roots),
+            rules: discover(ConfigKind::Rules, search_roots),
+        }
+    }
+}
+
+impl fmt::Display for ConfigSources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "agents from {}; llm-tags from {}; rules from {}",
+            self.agents, self.llm_tags, self.rules
+        )
+    }
+}
+
+/// Candidate sources for one config, highest priority first and ending with `Bundled`
+pub fn candidates(kind: ConfigKind, search_roots: &[PathBuf]) -> Vec<ConfigSource> {
+    if let Ok(path) = std::env::var(kind.env_var()) {
+        return vec![ConfigSource::Env(PathBuf::from(path))];
+    }
+
+    let roots: Vec<PathBuf> = if search_roots.is_empty() {
+        std::env::current_dir().into_iter().collect()
+    } else {
+        search_roots.to_vec()
+    };
+
+    let mut candidates = Vec::new();
+    for root in &roots {
+        for dir in repository_dirs(root) {
+            for name in kind.file_names() {
+                let candidate =
+                    ConfigSource::Repository(dir.join(REPOSITORY_CONFIG_DIR).join(name));
+                if !candidates.contains(&candidate) {
+                    candidates.push(candidate);
+                }
+            }
+        }
+    }
+    if let Some(dir) = user_config_dir() {
+        candidates.extend(
+            kind.file_names()
+                .map(|name| ConfigSource::User(dir.join(name))),
+        );
+    }
+    candidates.extend(
+        kind.file_names()
+            .map(|name| ConfigSource::WorkingDir(Path::new("./config").join(name))),
+    );
+    candidates.push(ConfigSource::Bundled);
+    candidates
+}
+
+/// The source one config is loaded from
+pub fn discover(kind: ConfigKind, search_roots: &[PathBuf]) -> ConfigSource {
+    candidates(kind, search_roots)
+        .into_iter()
+        .find(ConfigSource::exists)
+        .unwrap_or(ConfigSource::Bundled)
+}
+
+/// Files whose creation, change or removal can change the loaded configs: for each config,
+/// every candidate up to and including the one that is used
+pub fn watch_paths(search_roots: &[PathBuf]) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for kind in ConfigKind::ALL {
+        for candidate in candidates(kind, search_roots) {
+            if let Some(path) = candidate.path() {
+                paths.push(path.to_path_buf());
+            }
+            if candidate.exists() {
+                break;
+            }
+        }
+    }
+    paths
+}
+
+/// `dir` and its parents up to and including the git worktree root (the first one with a
+/// `.git` entry). Outside a worktree only `dir` itself is searched.
+fn repository_dirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for ancestor in dir.ancestors() {
+        dirs.push(ancestor.to_path_buf());
+        if ancestor.join(".git").exists() {
+            return dirs;
+        }
+    }
+    vec![dir.to_path_buf()]
+}
+
+/// `$XDG_CONFIG_HOME/agent-router`, else `~/.config/agent-router`
+fn user_config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .or_else(|| std::env::var_os("USERPROFILE"))
+                .map(|home| PathBuf::from(home).join(".config"))
+        })?;
+    Some(base.join("agent-router"))
+}
+
+/// The local path of a `file://` URI, the form MCP roots come in. Other schemes and
+/// remote hosts are not searched.
+pub fn path_from_file_uri(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    let path = rest.strip_prefix("localhost").unwrap_or(rest);
+    if !path.starts_with('/') {
+        return None;
+    }
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    let decoded = String::from_utf8(decoded).ok()?;
+
+    // file:///C:/src -> C:/src
+    if cfg!(windows) && decoded.as_bytes().get(2) == Some(&b':') {
+        return Some(PathBuf::from(&decoded[1..]));
+    }
+    Some(PathBuf::from(decoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    #[serial_test::serial]
+    fn test_discover_prefers_repository_then_user_dir() {
+        let base = std::env::temp_dir().join(format!(
+            "agent-router-discovery-test-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&base);
+        let repo = base.join("repo");
+        let nested = repo.join("crates").join("app");
+        let xdg = base.join("xdg");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(repo.join(REPOSITORY_CONFIG_DIR)).unwrap();
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir_all(xdg.join("agent-router")).unwrap();
+        // Above the worktree root, so never searched
+        fs::create_dir_all(base.join(REPOSITORY_CONFIG_DIR)).unwrap();
+        fs::write(base.join(REPOSITORY_CONFIG_DIR).join("agents.json"), "{}").unwrap();
+
+        let repo_rules = repo.join(REPOSITORY_CONFIG_DIR).join("rules.json");
+        let user_agents = xdg.join("agent-router").join("agents.json");
+        fs::write(&repo_rules, "{}").unwrap();
+        fs::write(&user_agents, "{}").unwrap();
+
+        std::env::remove_var("AGENTS_CONFIG_PATH");
+        std::env::remove_var("RULES_CONFIG_PATH");
+        std::env::set_var("LLM_TAGS_CONFIG_PATH", "/nonexistent/llm-tags.json");
+        std::env::set_var("XDG_CONFIG_HOME", &xdg);
+        let roots = vec![nested.clone()];
+
+        assert_eq!(
+            discover(ConfigKind::Rules, &roots),
+            ConfigSource::Repository(repo_rules.clone())
+        );
+        assert_eq!(
+            discover(ConfigKind::Agents, &roots),
+            ConfigSource::User(user_agents.clone())
+        );
+        assert_eq!(
+            discover(ConfigKind::LlmTags, &roots),
+            ConfigSource::Env(PathBuf::from("/nonexistent/llm-tags.json"))
+        );
+
+        // Creating a nearer or higher-priority file must be noticed
+        let paths = watch_paths(&roots);
+        assert!(paths.contains(&nested.join(REPOSITORY_CONFIG_DIR).join("agents.json")));
+        assert!(paths.contains(&repo.join(REPOSITORY_CONFIG_DIR).join("agents.json")));
+        assert!(paths.contains(&user_agents));
+        assert!(paths.contains(&repo_rules));
+        assert!(!paths.contains(&xdg.join("agent-router").join("rules.json")));
+        assert!(!paths.contains(&base.join(REPOSITORY_CONFIG_DIR).join("agents.json")));
+
+        std::env::remove_var("LLM_TAGS_CONFIG_PATH");
+        std::env::remove_var("XDG_CONFIG_HOME");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_path_from_file_uri() {
+        assert_eq!(
+            path_from_file_uri("file:///home/me/my%20repo"),
+            Some(PathBuf::from("/home/me/my repo"))
+        );
+        assert_eq!(
+            path_from_file_uri("file://localhost/srv/repo"),
+            Some(PathBuf::from("/srv/repo"))
+        );
+        assert_eq!(path_from_file_uri("file://server/share"), None);
+        assert_eq!(path_from_file_uri("https://example.com/repo"), None);
+        assert_eq!(path_from_file_uri("file:///bad%zz"), None);
+    }
+}
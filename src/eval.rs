@@ -0,0 +1,509 @@
+// Tagging accuracy evaluation for the `eval` subcommand.
+//
+// Runs `ModelManager::identify_tags` over a labelled JSONL dataset and scores the result
+// per tag. Reports are serializable so a run can be saved and compared against later
+// runs while tuning tag descriptions and examples.
+use crate::model_manager::ModelManager;
+use crate::types::{ClassificationInput, LlmTagConfig};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+use tracing::warn;
+
+/// One labelled line of a dataset
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalCase {
+    pub task: String,
+    pub intent: String,
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// Tags the LLM should identify; anything else it returns is a false positive
+    pub expected_tags: Vec<String>,
+}
+
+/// True/false positive counts and the scores derived from them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scores {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+impl Scores {
+    fn from_counts(true_positives: usize, false_positives: usize, false_negatives: usize) -> Self {
+        let ratio = |num: usize, den: usize| {
+            if den == 0 {
+                0.0
+            } else {
+                num as f64 / den as f64
+            }
+        };
+        let precision = ratio(true_positives, true_positives + false_positives);
+        let recall = ratio(true_positives, true_positives + false_negatives);
+        let f1 = if precision + recall == 0.0 {
+            0.0
+        } else {
+            2.0 * precision * recall / (precision + recall)
+        };
+        Self {
+            true_positives,
+            false_positives,
+            false_negatives,
+            precision,
+            recall,
+            f1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagScores {
+    pub tag: String,
+    #[serde(flatten)]
+    pub scores: Scores,
+}
+
+/// How often `predicted` was returned in a case where `expected` was missed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Confusion {
+    pub expected: String,
+    pub predicted: String,
+    pub count: usize,
+}
+
+/// Nearest-rank latency percentiles of successful cases, in milliseconds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+impl LatencyStats {
+    fn from_samples(mut samples: Vec<u64>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        let percentile = |p: usize| samples[(p * samples.len()).div_ceil(100).max(1) - 1];
+        Self {
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalReport {
+    pub cases: usize,
+    /// Cases where tagging failed, as "case N: error"; they are not scored
+    pub errors: Vec<String>,
+    /// Micro-averaged over all tags
+    pub overall: Scores,
+    /// Every tag that was expected or predicted, in name order
+    pub tags: Vec<TagScores>,
+    /// Most frequent first
+    pub confusions: Vec<Confusion>,
+    pub latency_ms: LatencyStats,
+}
+
+/// Load a JSONL dataset; blank lines are skipped
+pub fn load_dataset(path: &Path) -> Result<Vec<EvalCase>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read dataset from {}", path.display()))?;
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Failed to parse {} line {}", path.display(), idx + 1))
+        })
+        .collect()
+}
+
+/// Tag every case and score the results against the expected tags
+pub async fn run_eval(
+    model_manager: &ModelManager,
+    tag_config: &LlmTagConfig,
+    cases: &[EvalCase],
+) -> EvalReport {
+    // tag -> (true positives, false positives, false negatives)
+    let mut counts: BTreeMap<String, (usize, usize, usize)> = BTreeMap::new();
+    let mut confusions: BTreeMap<(String, String), usize> = BTreeMap::new();
+    let mut latencies = Vec::new();
+    let mut errors = Vec::new();
+
+    for (idx, case) in cases.iter().enumerate() {
+        let input = ClassificationInput {
+            task: case.task.clone(),
+            intent: case.intent.clone(),
+            original_prompt: case.prompt.clone(),
+            associated_files: (!case.files.is_empty()).then(|| case.files.clone()),
+            git_context: None,
+            agent_config_path: None,
+            rules_config_path: None,
+            llm_tags_path: None,
+        };
+
+        let started = Instant::now();
+        let predicted: Vec<String> = match model_manager.identify_tags(&input, tag_config).await {
+            Ok(tags) => tags.into_iter().map(|t| t.tag).collect(),
+            Err(e) => {
+                warn!("Eval case {} failed: {}", idx + 1, e);
+                errors.push(format!("case {}: {:#}", idx + 1, e));
+                continue;
+            }
+        };
+        latencies.push(started.elapsed().as_millis() as u64);
+
+        let missed: Vec<&String> = case
+            .expected_tags
+            .iter()
+            .filter(|t| !predicted.contains(t))
+            .collect();
+        let spurious: Vec<&String> = predicted
+            .iter()
+            .filter(|t| !case.expected_tags.contains(t))
+            .collect();
+
+        for tag in &case.expected_tags {
+            let entry = counts.entry(tag.clone()).or_default();
+            if predicted.contains(tag) {
+                entry.0 += 1;
+            } else {
+                entry.2 += 1;
+            }
+        }
+        for tag in &spurious {
+            counts.entry((*tag).clone()).or_default().1 += 1;
+        }
+        for expected in &missed {
+            for predicted in &spurious {
+                *confusions
+                    .entry(((*expected).clone(), (*predicted).clone()))
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    let (tp, fp, fn_) = counts
+        .values()
+        .fold((0, 0, 0), |acc, c| (acc.0 + c.0, acc.1 + c.1, acc.2 + c.2));
+    let mut confusions: Vec<Confusion> = confusions
+        .into_iter()
+        .map(|((expected, predicted), count)| Confusion {
+            expected,
+            predicted,
+            count,
+        })
+        .collect();
+    confusions.sort_by_key(|c| std::cmp::Reverse(c.count));
+
+    EvalReport {
+        cases: cases.len(),
+        errors,
+        overall: Scores::from_counts(tp, fp, fn_),
+        tags: counts
+            .into_iter()
+            .map(|(tag, (tp, fp, fn_))| TagScores {
+                tag,
+                scores: Scores::from_counts(tp, fp, fn_),
+            })
+            .collect(),
+        confusions,
+        latency_ms: LatencyStats::from_samples(latencies),
+    }
+}
+
+/// Load a report written by a previous `eval --save`
+pub fn load_report(path: &Path) -> Result<EvalReport> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read report from {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse report from {}", path.display()))
+}
+
+/// Changes from a baseline report to the current one (current minus baseline)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportDiff {
+    pub overall_f1: f64,
+    /// F1 change per tag present in either report; a tag missing from one counts as 0
+    pub tags: Vec<(String, f64)>,
+    pub latency_p50_ms: i64,
+    pub latency_p90_ms: i64,
+}
+
+pub fn diff_reports(baseline: &EvalReport, current: &EvalReport) -> ReportDiff {
+    let f1_of = |report: &EvalReport, tag: &str| {
+        report
+            .tags
+            .iter()
+            .find(|t| t.tag == tag)
+            .map(|t| t.scores.f1)
+            .unwrap_or(0.0)
+    };
+
+    let mut tags: Vec<&str> = baseline
+        .tags
+        .iter()
+        .chain(&current.tags)
+        .map(|t| t.tag.as_str())
+        .collect();
+    tags.sort_unstable();
+    tags.dedup();
+
+    ReportDiff {
+        overall_f1: current.overall.f1 - baseline.overall.f1,
+        tags: tags
+            .into_iter()
+            .map(|tag| (tag.to_string(), f1_of(current, tag) - f1_of(baseline, tag)))
+            .collect(),
+        latency_p50_ms: current.latency_ms.p50 as i64 - baseline.latency_ms.p50 as i64,
+        latency_p90_ms: current.latency_ms.p90 as i64 - baseline.latency_ms.p90 as i64,
+    }
+}
+
+impl fmt::Display for EvalReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} cases, {} errors", self.cases, self.errors.len())?;
+        for error in &self.errors {
+            writeln!(f, "  {}", error)?;
+        }
+
+        writeln!(
+            f,
+            "\n{:<28} {:>5} {:>5} {:>5} {:>9} {:>6} {:>6}",
+            "tag", "tp", "fp", "fn", "precision", "recall", "f1"
+        )?;
+        let rows = self
+            .tags
+            .iter()
+            .map(|t| (t.tag.as_str(), &t.scores))
+            .chain(std::iter::once(("(overall)", &self.overall)));
+        for (tag, s) in rows {
+            writeln!(
+                f,
+                "{:<28} {:>5} {:>5} {:>5} {:>9.3} {:>6.3} {:>6.3}",
+                tag,
+                s.true_positives,
+                s.false_positives,
+                s.false_negatives,
+                s.precision,
+                s.recall,
+                s.f1
+            )?;
+        }
+
+        if !self.confusions.is_empty() {
+            writeln!(f, "\nMissed tag -> returned instead")?;
+            for c in &self.confusions {
+                writeln!(f, "  {} -> {} ({}x)", c.expected, c.predicted, c.count)?;
+            }
+        }
+
+        write!(
+            f,
+            "\nLatency ms: p50 {}, p90 {}, p99 {}, max {}",
+            self.latency_ms.p50, self.latency_ms.p90, self.latency_ms.p99, self.latency_ms.max
+        )
+    }
+}
+
+impl fmt::Display for ReportDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "F1 change vs baseline: {:+.3} overall", self.overall_f1)?;
+        for (tag, delta) in self.tags.iter().filter(|(_, d)| d.abs() >= 0.0005) {
+            writeln!(f, "  {:<28} {:+.3}", tag, delta)?;
+        }
+        write!(
+            f,
+            "Latency change: p50 {:+} ms, p90 {:+} ms",
+            self.latency_p50_ms, self.latency_p90_ms
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{self, GenerateRequest, LlmBackend};
+    use crate::types::{Config, LlmTagDefinition};
+
+    /// Replies with the first canned answer whose key appears in the prompt
+    struct ScriptedBackend {
+        replies: Vec<(&'static str, &'static str)>,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmBackend for ScriptedBackend {
+        fn name(&self) -> &'static str {
+            "Scripted"
+        }
+
+        fn not_installed_message(&self) -> String {
+            String::new()
+        }
+
+        fn check_installed(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn is_running(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn start(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn generate(&self, request: &GenerateRequest) -> Result<backend::GenerateResponse> {
+            let (_, reply) = self
+                .replies
+                .iter()
+                .find(|(key, _)| request.prompt.contains(key))
+                .ok_or_else(|| anyhow::anyhow!("connection refused"))?;
+            Ok(backend::GenerateResponse {
+                text: reply.to_string(),
+                thinking: None,
+            })
+        }
+
+        async fn list_models(&self) -> Result<Vec<String>> {
+            Ok(vec![])
+        }
+
+        async fn is_loaded(&self, _model: &str) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn pull(
+            &self,
+            _model: &str,
+            _on_progress: &mut (dyn FnMut(u8) + Send),
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn load(&self, _model: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn tag(name: &str) -> LlmTagDefinition {
+        LlmTagDefinition {
+            name: name.to_string(),
+            description: name.to_string(),
+            examples: vec![],
+            min_confidence: None,
+        }
+    }
+
+    fn case(task: &str, expected_tags: &[&str]) -> EvalCase {
+        EvalCase {
+            task: task.to_string(),
+            intent: "implement feature".to_string(),
+            prompt: None,
+            files: vec![],
+            expected_tags: expected_tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_eval_scores_tags() {
+        let manager = ModelManager::with_backend(
+            Config::default(),
+            Box::new(ScriptedBackend {
+                replies: vec![
+                    (
+                        "login form",
+                        r#"{"tags": [{"name": "security", "confidence": 90}]}"#,
+                    ),
+                    (
+                        "password reset",
+                        r#"{"tags": [{"name": "api", "confidence": 80}]}"#,
+                    ),
+                    (
+                        "cache layer",
+                        r#"{"tags": [{"name": "performance", "confidence": 70}, {"name": "api", "confidence": 60}]}"#,
+                    ),
+                ],
+            }),
+        );
+        let tag_config = LlmTagConfig {
+            tags: vec![tag("security"), tag("api"), tag("performance")],
+        };
+        let cases = vec![
+            case("Build the login form", &["security"]),
+            case("Add password reset", &["security"]),
+            case("Add a cache layer", &["performance"]),
+            case("Unknown task", &["api"]),
+        ];
+
+        let report = run_eval(&manager, &tag_config, &cases).await;
+
+        assert_eq!(report.cases, 4);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].starts_with("case 4:"));
+        let security = &report
+            .tags
+            .iter()
+            .find(|t| t.tag == "security")
+            .unwrap()
+            .scores;
+        assert_eq!((security.true_positives, security.false_negatives), (1, 1));
+        assert_eq!(security.precision, 1.0);
+        assert_eq!(security.recall, 0.5);
+        let api = &report.tags.iter().find(|t| t.tag == "api").unwrap().scores;
+        assert_eq!((api.true_positives, api.false_positives), (0, 2));
+        assert_eq!(api.f1, 0.0);
+        assert_eq!(
+            report.overall,
+            Scores::from_counts(2, 2, 1),
+            "micro-average over all tags"
+        );
+        assert_eq!(
+            report.confusions,
+            vec![Confusion {
+                expected: "security".to_string(),
+                predicted: "api".to_string(),
+                count: 1,
+            }]
+        );
+
+        let mut baseline = report.clone();
+        baseline.tags[0].scores.f1 = 0.5;
+        baseline.overall.f1 = 0.5;
+        let diff = diff_reports(&baseline, &report);
+        assert!((diff.overall_f1 - (report.overall.f1 - 0.5)).abs() < 1e-9);
+        assert_eq!(diff.tags[0], ("api".to_string(), -0.5));
+    }
+
+    #[test]
+    fn test_latency_percentiles() {
+        let stats = LatencyStats::from_samples((1..=10).rev().collect());
+        assert_eq!(
+            stats,
+            LatencyStats {
+                p50: 5,
+                p90: 9,
+                p99: 10,
+                max: 10
+            }
+        );
+        assert_eq!(LatencyStats::from_samples(vec![]), LatencyStats::default());
+    }
+}
@@ -0,0 +1,331 @@
+// Layered configs.
+//
+// A config file can name base files in `extends` (a path or a list of paths, relative to
+// the file). The bases are merged in order, then the file itself on top:
+//
+// - agents and tags are matched by `name`: an entry with an inherited name overrides only
+//   the fields it sets, a new name is appended
+// - rules are matched by `id` the same way; rules without an `id` are appended
+// - `disable_rules` drops inherited rules by `id`
+// - any other top-level key replaces the inherited value
+//
+// Merging works on the parsed files before they are deserialized, so overrides can be partial.
+use crate::discovery::ConfigKind;
+use crate::format::ConfigFormat;
+use crate::rules;
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Whether a parsed config file has to be merged with other files
+pub(crate) fn is_layered(value: &Value) -> bool {
+    value.get("extends").is_some() || value.get("disable_rules").is_some()
+}
+
+/// Merge a parsed config file (at its canonical `path`) with the files it extends
+pub(crate) fn resolve(path: &Path, value: Value, kind: ConfigKind) -> Result<Value> {
+    let mut chain = vec![path.to_path_buf()];
+    resolve_layers(path, value, kind, &mut chain).map(Value::Object)
+}
+
+fn resolve_layers(
+    path: &Path,
+    value: Value,
+    kind: ConfigKind,
+    chain: &mut Vec<PathBuf>,
+) -> Result<Map<String, Value>> {
+    let Value::Object(mut object) = value else {
+        bail!("{} must contain an object at the top level", path.display());
+    };
+    let extends = string_list(object.remove("extends"), "extends", path)?;
+    let disabled = string_list(object.remove("disable_rules"), "disable_rules", path)?;
+    if !disabled.is_empty() && kind != ConfigKind::Rules {
+        bail!(
+            "`disable_rules` in {} is only allowed in rules configs",
+            path.display()
+        );
+    }
+
+    let mut inherited: Option<Map<String, Value>> = None;
+    for base in &extends {
+        let base_path = base_path(path, base)?;
+        if chain.contains(&base_path) {
+            let cycle: Vec<String> = chain
+                .iter()
+                .chain([&base_path])
+                .map(|p| p.display().to_string())
+                .collect();
+            bail!("Config extends cycle: {}", cycle.join(" -> "));
+        }
+
+        let content = fs::read_to_string(&base_path)
+            .with_context(|| format!("Failed to read {}", base_path.display()))?;
+        let value: Value = parse(&base_path, &content)
+            .with_context(|| format!("Failed to parse {}", base_path.display()))?;
+
+        chain.push(base_path.clone());
+        let base = resolve_layers(&base_path, value, kind, chain)?;
+        chain.pop();
+
+        inherited = Some(match inherited {
+            None => base,
+            Some(merged) => merge(merged, base, kind),
+        });
+    }
+
+    let mut inherited = inherited.unwrap_or_default();
+    for id in &disabled {
+        let rules = inherited.get_mut("rules").and_then(Value::as_array_mut);
+        let position = rules
+            .as_ref()
+            .and_then(|rules| rules.iter().position(|r| entry_key(r, "id") == Some(id)));
+        match (rules, position) {
+            (Some(rules), Some(idx)) => {
+                rules.remove(idx);
+            }
+            _ => bail!(
+                "`disable_rules` in {}: no inherited rule has id '{}'",
+                path.display(),
+                id
+            ),
+        }
+    }
+
+    Ok(merge(inherited, object, kind))
+}
+
+/// Every file merged into the config at `path`, starting with `path` itself. Files that
+/// cannot be read are skipped; loading the config reports them.
+pub(crate) fn layer_files(path: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_layer_files(path, &mut files);
+    files
+}
+
+fn collect_layer_files(path: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(path) = path.canonicalize() else {
+        return;
+    };
+    if files.contains(&path) {
+        return;
+    }
+    files.push(path.clone());
+
+    let value = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| parse(&path, &content).ok());
+    let Some(mut value) = value else {
+        return;
+    };
+    let extends = value.as_object_mut().and_then(|o| o.remove("extends"));
+    for base in string_list(extends, "extends", &path).unwrap_or_default() {
+        if let Some(dir) = path.parent() {
+            collect_layer_files(&dir.join(base), files);
+        }
+    }
+}
+
+/// Resolve an `extends` entry relative to the file naming it, with the same checks as
+/// any other config path
+fn base_path(path: &Path, base: &str) -> Result<PathBuf> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    rules::validate_config_path(&dir.join(base).to_string_lossy())
+        .with_context(|| format!("Invalid `extends` entry '{}' in {}", base, path.display()))
+}
+
+fn parse(path: &Path, content: &str) -> Result<Value> {
+    ConfigFormat::from_path(path)
+        .unwrap_or(ConfigFormat::Json)
+        .parse(content)
+}
+
+/// A string or a list of strings
+fn string_list(value: Option<Value>, key: &str, path: &Path) -> Result<Vec<String>> {
+    let invalid = || {
+        anyhow::anyhow!(
+            "`{}` in {} must be a string or a list of strings",
+            key,
+            path.display()
+        )
+    };
+    match value {
+        None => Ok(vec![]),
+        Some(Value::String(s)) => Ok(vec![s]),
+        Some(Value::Array(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(s) => Ok(s),
+                _ => Err(invalid()),
+            })
+            .collect(),
+        Some(_) => Err(invalid()),
+    }
+}
+
+/// The list a config holds and the field its entries are matched by
+fn entries_of(kind: ConfigKind) -> (&'static str, &'static str) {
+    match kind {
+        ConfigKind::Agents => ("agents", "name"),
+        ConfigKind::LlmTags => ("tags", "name"),
+        ConfigKind::Rules => ("rules", "id"),
+    }
+}
+
+fn entry_key<'a>(entry: &'a Value, field: &str) -> Option<&'a str> {
+    entry.get(field).and_then(Value::as_str)
+}
+
+/// Put `overlay` on top of `base`
+fn merge(
+    mut base: Map<String, Value>,
+    overlay: Map<String, Value>,
+    kind: ConfigKind,
+) -> Map<String, Value> {
+    let (list, field) = entries_of(kind);
+
+    for (key, value) in overlay {
+        match (key == list, base.get_mut(&key), value) {
+            (true, Some(Value::Array(entries)), Value::Array(overrides)) => {
+                for entry in overrides {
+                    let position = entry_key(&entry, field).and_then(|key| {
+                        entries
+                            .iter()
+                            .position(|e| entry_key(e, field) == Some(key))
+                    });
+                    match (position.map(|idx| &mut entries[idx]), entry) {
+                        (Some(Value::Object(existing)), Value::Object(fields)) => {
+                            existing.extend(fields)
+                        }
+                        (_, entry) => entries.push(entry),
+                    }
+                }
+            }
+            (_, _, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+    base
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{RulesConfig, UserConfig};
+
+    fn write(dir: &Path, name: &str, json: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, json).unwrap();
+        path
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("base")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_extends_overrides_and_appends_entries() {
+        let dir = temp_dir("agent-router-extends-test");
+        write(
+            &dir.join("base"),
+            "agents.json",
+            r#"{"agents": [
+                {"name": "rust-reviewer", "description": "Rust", "priority": 60},
+                {"name": "security-auditor", "description": "Security"}
+            ]}"#,
+        );
+        let path = write(
+            &dir,
+            "agents.json",
+            r#"{"extends": "base/agents.json", "agents": [
+                {"name": "rust-reviewer", "instructions": "Check unsafe blocks"},
+                {"name": "api-designer", "description": "APIs"}
+            ]}"#,
+        );
+
+        let config: UserConfig = rules::load_user_config(&path.to_string_lossy()).unwrap();
+        let names: Vec<&str> = config.agents.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["rust-reviewer", "security-auditor", "api-designer"]);
+        assert_eq!(config.agents[0].description, "Rust");
+        assert_eq!(config.agents[0].priority, 60);
+        assert_eq!(
+            config.agents[0].instructions.as_deref(),
+            Some("Check unsafe blocks")
+        );
+        assert_eq!(
+            layer_files(&path),
+            vec![
+                path.canonicalize().unwrap(),
+                dir.join("base/agents.json").canonicalize().unwrap()
+            ]
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_extends_disables_and_overrides_rules_by_id() {
+        let dir = temp_dir("agent-router-extends-rules-test");
+        write(
+            &dir.join("base"),
+            "rules.json",
+            r#"{"rules": [
+                {"id": "rust", "conditions": {"file_pattern": "*.rs"}, "route_to_subagents": ["rust-reviewer"]},
+                {"id": "docs", "conditions": {"file_pattern": "*.md"}, "route_to_subagents": ["docs-reviewer"]}
+            ]}"#,
+        );
+        let path = write(
+            &dir,
+            "rules.json",
+            r#"{"extends": ["base/rules.json"], "disable_rules": ["docs"], "rules": [
+                {"id": "rust", "conditions": {"file_pattern": "src/**/*.rs"}},
+                {"conditions": {"llm_tag": "security"}, "route_to_subagents": ["security-auditor"]}
+            ]}"#,
+        );
+
+        let config: RulesConfig = rules::load_rules_config(&path.to_string_lossy()).unwrap();
+        assert_eq!(config.rules.len(), 2);
+        assert_eq!(config.rules[0].id.as_deref(), Some("rust"));
+        assert_eq!(config.rules[0].route_to_subagents, ["rust-reviewer"]);
+        assert_eq!(
+            serde_json::to_value(&config.rules[0].conditions).unwrap(),
+            serde_json::json!({"file_pattern": "src/**/*.rs"})
+        );
+        assert_eq!(config.rules[1].route_to_subagents, ["security-auditor"]);
+
+        write(
+            &dir,
+            "rules.json",
+            r#"{"extends": "base/rules.json", "disable_rules": ["legacy"], "rules": []}"#,
+        );
+        let err = rules::load_rules_config(&path.to_string_lossy()).unwrap_err();
+        assert!(format!("{:#}", err).contains("no inherited rule has id 'legacy'"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_extends_cycle_is_an_error() {
+        let dir = temp_dir("agent-router-extends-cycle-test");
+        write(
+            &dir.join("base"),
+            "llm-tags.json",
+            r#"{"extends": "../llm-tags.json", "tags": []}"#,
+        );
+        let path = write(
+            &dir,
+            "llm-tags.json",
+            r#"{"extends": "base/llm-tags.json", "tags": []}"#,
+        );
+
+        let err = rules::load_llm_tag_config(&path.to_string_lossy()).unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("Config extends cycle"),
+            "{:#}",
+            err
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
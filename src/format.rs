@@ -0,0 +1,144 @@
+// Config file formats.
+//
+// Agents, rules and tags can be written as JSON, YAML or TOML; the file extension picks
+// the format. YAML block scalars and TOML literal strings avoid the escaping JSON needs
+// for long instructions and regexes.
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Supported extensions, in the order discovery prefers them within one directory
+    pub const EXTENSIONS: [&'static str; 4] = ["json", "yaml", "yml", "toml"];
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(ConfigFormat::Json),
+            "yaml" | "yml" => Some(ConfigFormat::Yaml),
+            "toml" => Some(ConfigFormat::Toml),
+            _ => None,
+        }
+    }
+
+    /// Deserialize a whole file. Errors name the path of the failing node (e.g.
+    /// `rules[3].conditions.any_of[1]`) and its line and column.
+    pub fn parse<T: DeserializeOwned>(self, content: &str) -> Result<T> {
+        match self {
+            ConfigFormat::Json => {
+                let mut deserializer = serde_json::Deserializer::from_str(content);
+                let value = with_path(serde_path_to_error::deserialize(&mut deserializer))?;
+                deserializer.end()?;
+                Ok(value)
+            }
+            ConfigFormat::Yaml => with_path(serde_path_to_error::deserialize(
+                serde_yaml_ng::Deserializer::from_str(content),
+            )),
+            ConfigFormat::Toml => with_path(serde_path_to_error::deserialize(
+                toml::Deserializer::new(content),
+            )),
+        }
+    }
+}
+
+/// Deserialize a config merged from several files. Errors name the path of the failing
+/// node in the merged config; there is no single file to give a line for.
+pub(crate) fn from_merged<T: DeserializeOwned>(value: serde_json::Value) -> Result<T> {
+    with_path(serde_path_to_error::deserialize(value))
+        .context("Invalid config after merging `extends`")
+}
+
+fn with_path<T, E: std::error::Error + Send + Sync + 'static>(
+    result: Result<T, serde_path_to_error::Error<E>>,
+) -> Result<T> {
+    result.map_err(|err| {
+        let path = err.path().to_string();
+        let inner = err.into_inner();
+        if path == "." {
+            anyhow::Error::new(inner)
+        } else {
+            anyhow::anyhow!("{}: {}", path, inner)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{RulesConfig, UserConfig};
+
+    #[test]
+    fn test_formats_parse_the_same_config() {
+        let json = r#"{"rules": [{"conditions": {"any_of": [{"file_regex": "\\.rs$"}, {"llm_tag": "rust"}]},
+                                   "route_to_subagents": ["rust-reviewer"]}]}"#;
+        let yaml = r#"
+rules:
+  - conditions:
+      any_of:
+        - file_regex: '\.rs$'
+        - llm_tag: rust
+    route_to_subagents: [rust-reviewer]
+"#;
+        let toml = r#"
+[[rules]]
+route_to_subagents = ["rust-reviewer"]
+conditions.any_of = [{ file_regex = '\.rs$' }, { llm_tag = "rust" }]
+"#;
+
+        let expected =
+            serde_json::to_value(ConfigFormat::Json.parse::<RulesConfig>(json).unwrap()).unwrap();
+        for (format, content) in [(ConfigFormat::Yaml, yaml), (ConfigFormat::Toml, toml)] {
+            let config: RulesConfig = format.parse(content).unwrap();
+            assert_eq!(
+                serde_json::to_value(config).unwrap(),
+                expected,
+                "{:?}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn test_condition_errors_name_the_rule_and_path() {
+        let json = r#"{"rules": [
+            {"conditions": {"file_pattern": "*.rs"}, "route_to_subagents": ["rust-reviewer"]},
+            {"conditions": {"any_of": [{"llm_tag": "rust"}, {"file_patern": "*.rs"}]},
+             "route_to_subagents": ["rust-reviewer"]}
+        ]}"#;
+        let err = ConfigFormat::Json
+            .parse::<RulesConfig>(json)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with("rules[1].conditions.any_of[1]: unknown condition key `file_patern`"),
+            "{}",
+            err
+        );
+        assert!(err.contains("line 3"), "{}", err);
+
+        let merged = serde_json::from_str(json).unwrap();
+        let err = from_merged::<RulesConfig>(merged).unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("rules[1].conditions.any_of[1]"),
+            "{:#}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_line() {
+        let yaml = "agents:\n  - name: rust-reviewer\n    priority: 50\n";
+        let err = ConfigFormat::Yaml.parse::<UserConfig>(yaml).unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+
+        let toml = "[[agents]]\nname = \"rust-reviewer\"\npriority = \"high\"\n";
+        let err = ConfigFormat::Toml.parse::<UserConfig>(toml).unwrap_err();
+        assert!(err.to_string().contains("line 3"), "{}", err);
+    }
+}
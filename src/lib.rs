@@ -1,12 +1,41 @@
 // Public exports for integration testing
+mod backend;
 mod classifier;
+mod discovery;
+mod eval;
+mod extends;
+mod format;
+mod lint;
 mod model_manager;
+mod reload;
+mod rule_tests;
 mod rules;
+mod schema;
 mod types;
+mod validation;
 
+pub use backend::{BackendError, GenerateRequest, GenerateResponse, LlmBackend};
 pub use classifier::Classifier;
+pub use discovery::{discover, path_from_file_uri, ConfigKind, ConfigSource, ConfigSources};
+pub use eval::{
+    diff_reports, load_dataset, load_report, run_eval, Confusion, EvalCase, EvalReport,
+    LatencyStats, ReportDiff, Scores, TagScores,
+};
+pub use format::ConfigFormat;
+pub use lint::{lint_configs, Diagnostic};
 pub use model_manager::ModelManager;
+pub use reload::{ConfigDiff, ConfigSet, ConfigWatcher, ListDiff};
+pub use rule_tests::{
+    load_rule_tests, run_rule_tests, ConfidenceBounds, InjectedTag, Mismatch, RuleTestCase,
+    RuleTestExpectation, RuleTestInput, RuleTestResult, RuleTestSuite,
+};
+pub use rules::{
+    default_llm_tag_config, default_rules_config, default_user_config, load_llm_tag_config,
+    load_rules_config, load_user_config,
+};
+pub use schema::config_schema;
 pub use types::*;
+pub use validation::{validate_configs, ConfigIssue, Severity};
 
 // Re-export the server handler for integration tests
 use async_trait::async_trait;
@@ -154,8 +183,8 @@ impl RouterServerHandler {
 
     fn create_tool(name: &str, description: &str) -> Tool {
         let input_schema = match name {
-            "get_instructions" => {
-                // get_instructions requires task and intent
+            "get_instructions" | "explain_routing" => {
+                // get_instructions and explain_routing require task and intent
                 // original_prompt is optional (for better LLM tagging)
                 // associated_files is optional (for file-based routing)
                 // git_context is auto-detected from the current working directory (branch only)
@@ -198,8 +227,25 @@ impl RouterServerHandler {
                     None,
                 )
             }
+            "get_config_schema" => {
+                use serde_json::json;
+                use std::collections::HashMap;
+
+                let mut properties = HashMap::new();
+                let config_props = json!({
+                        "type": "string",
+                        "enum": ["agents", "rules", "tags"],
+                        "description": "Which config file to describe: agents.json, rules.json or llm-tags.json"
+                    })
+                    .as_object()
+                    .unwrap()
+                    .clone();
+                properties.insert("config".to_string(), config_props);
+
+                ToolInputSchema::new(vec!["config".to_string()], Some(properties), None)
+            }
             _ => {
-                // init_llm has no parameters - uses model name from config
+                // init_llm and reload_config have no parameters
                 ToolInputSchema::new(vec![], None, None)
             }
         };
@@ -217,6 +263,42 @@ impl RouterServerHandler {
         }
     }
 
+    /// Reload the configs now and report what changed. Fails, keeping the current
+    /// configs, if the files do not load or validate.
+    async fn handle_reload_config_tool(&self) -> std::result::Result<String, String> {
+        let state_lock = self.state.lock().await;
+        let classifier_cell = Arc::clone(&state_lock.classifier);
+        let strict_config = state_lock.config.strict_config;
+        let search_roots = state_lock.config.config_search_roots.clone();
+        drop(state_lock);
+
+        // Nothing is cached before the first routing request; only check the files
+        let Some(classifier) = classifier_cell.get() else {
+            let configs = Classifier::load_configs(strict_config, &search_roots)
+                .map_err(|e| format!("Config reload failed: {:#}", e))?;
+            return Ok(serde_json::json!({
+                "reloaded": false,
+                "summary": "Configs are valid and will be loaded by the first routing request",
+                "changes": ConfigDiff::default(),
+                "sources": configs.sources,
+            })
+            .to_string());
+        };
+
+        let diff = classifier
+            .reload_configs()
+            .map_err(|e| format!("Config reload failed, keeping previous configs: {:#}", e))?;
+        info!("Configs reloaded via reload_config: {}", diff);
+
+        Ok(serde_json::json!({
+            "reloaded": true,
+            "summary": diff.to_string(),
+            "changes": diff,
+            "sources": classifier.config_sources(),
+        })
+        .to_string())
+    }
+
     async fn handle_init_llm_tool(
         &self,
         runtime: Arc<dyn McpServer>,
@@ -230,76 +312,80 @@ impl RouterServerHandler {
             .map_err(|e| format!("Failed to create model manager: {}", e))?;
 
         let mut steps_performed: Vec<String> = vec![];
-
-        // Step 1: Check Ollama is installed
-        let ollama_installed = model_manager.check_ollama_installed().map_err(|_| {
-            "Could not verify Ollama installation. Ensure 'ollama' is in your PATH.".to_string()
+        let backend_name = model_manager.backend_name();
+
+        // Step 1: Check the backend runtime is installed
+        let installed = model_manager.check_backend_installed().map_err(|_| {
+            format!(
+                "Could not verify {} installation. Ensure it is in your PATH.",
+                backend_name
+            )
         })?;
 
-        if !ollama_installed {
-            return Ok(
-                r#"{"success":false,"message":"Ollama is not installed. Please install from https://ollama.com","steps_performed":[]}"#
-                    .to_string(),
-            );
+        if !installed {
+            let output = InitLlmOutput {
+                success: false,
+                message: model_manager.backend_not_installed_message(),
+                steps_performed,
+            };
+            return serde_json::to_string(&output).map_err(|e| e.to_string());
         }
 
-        // Step 2: Start Ollama if not running
-        let ollama_running = model_manager.check_ollama_running().await.map_err(|_| {
-            "Could not connect to Ollama. It may have stopped unexpectedly.".to_string()
+        // Step 2: Start the backend if not running
+        let running = model_manager.check_backend_running().await.map_err(|_| {
+            format!(
+                "Could not connect to {}. It may have stopped unexpectedly.",
+                backend_name
+            )
         })?;
 
-        if !ollama_running {
-            info!("Starting Ollama...");
-
-            use std::io::{BufRead, BufReader};
-            use std::process::{Command, Stdio};
-
-            let mut child = Command::new("ollama")
-                .arg("serve")
-                .stdout(Stdio::null())
-                .stderr(Stdio::piped())
-                .spawn()
-                .map_err(|e| {
-                    format!(
-                        "Could not start Ollama: {}. Try running 'ollama serve' manually.",
-                        e
-                    )
-                })?;
-
-            // Read stderr until we see "Listening on" which indicates Ollama is ready
-            let stderr = child.stderr.take().expect("stderr was piped");
-            let reader = BufReader::new(stderr);
-
-            let mut ready = false;
-            for line in reader.lines().map_while(std::result::Result::ok) {
-                if line.contains("Listening on") {
-                    ready = true;
-                    break;
-                }
-            }
-
-            // Detach the child process so it keeps running
-            std::mem::forget(child);
+        if !running {
+            let ready = model_manager
+                .start_backend()
+                .await
+                .map_err(|e| format!("Could not start {}: {:#}", backend_name, e))?;
 
             if !ready {
                 let output = InitLlmOutput {
                     success: false,
-                    message: "Ollama started but did not become ready. Try running 'ollama serve' manually to see errors.".to_string(),
+                    message: format!(
+                        "{} started but did not become ready. Start it manually to see errors.",
+                        backend_name
+                    ),
                     steps_performed,
                 };
                 return serde_json::to_string(&output).map_err(|e| e.to_string());
             }
 
-            info!("Ollama ready");
-            steps_performed.push("Started Ollama service".to_string());
+            info!("{} ready", backend_name);
+            steps_performed.push(format!("Started {} service", backend_name));
         } else {
-            info!("Ollama already running");
-            steps_performed.push("Ollama already running".to_string());
+            info!("{} already running", backend_name);
+            steps_performed.push(format!("{} already running", backend_name));
+        }
+
+        // Steps 3-4 only apply to runtimes that manage their own models
+        if !model_manager.backend_manages_models() {
+            steps_performed.push(format!(
+                "Model download not applicable for {}",
+                backend_name
+            ));
+            steps_performed.push(format!("Model loading not applicable for {}", backend_name));
+
+            let output = InitLlmOutput {
+                success: true,
+                message: "LLM ready for routing".to_string(),
+                steps_performed,
+            };
+            return serde_json::to_string(&output).map_err(|e| e.to_string());
         }
 
         // Step 3: Pull model if not installed
         let model_exists = model_manager.check_model_exists().await.map_err(|_| {
-            "Could not check model status. Ollama may have stopped. Run init_llm again.".to_string()
+            format!(
+                "Could not check model status. {} may have stopped. Run init_llm again.",
+                backend_name
+            )
         })?;
 
         let effective_name = config.effective_model_name();
@@ -355,8 +441,10 @@ impl RouterServerHandler {
 
         // Step 4: Load model into memory if not loaded
         let model_loaded = model_manager.check_model_loaded().await.map_err(|_| {
-            "Could not check if model is loaded. Ollama may have stopped. Run init_llm again."
-                .to_string()
+            format!(
+                "Could not check if model is loaded. {} may have stopped. Run init_llm again.",
+                backend_name
+            )
         })?;
 
         if !model_loaded {
@@ -369,8 +457,10 @@ impl RouterServerHandler {
                     let friendly_msg = if msg.contains("404") || msg.contains("not found") {
                         format!("Model '{}' not found. Check MODEL_NAME is correct and run init_llm again.", config.model_name)
                     } else if msg.contains("connection") || msg.contains("Connection") {
-                        "Could not connect to Ollama. It may have stopped. Run init_llm again."
-                            .to_string()
+                        format!(
+                            "Could not connect to {}. It may have stopped. Run init_llm again.",
+                            backend_name
+                        )
                     } else {
                         format!(
                             "Could not load model: {}. Run init_llm again to retry.",
@@ -399,12 +489,14 @@ impl RouterServerHandler {
         serde_json::to_string(&output).map_err(|e| e.to_string())
     }
 
+    /// Route a request. With `explain` set, returns the full `RoutingExplanation`
+    /// instead of the `InstructionsResponse`.
     async fn handle_get_instructions_tool(
         &self,
         params: serde_json::Value,
+        explain: bool,
     ) -> std::result::Result<String, String> {
         // Initialize classifier if needed (lazy initialization)
-        // Do this first to check Ollama status before validating input
         let state_lock = self.state.lock().await;
         let classifier_cell = Arc::clone(&state_lock.classifier);
         let config = state_lock.config.clone();
@@ -424,34 +516,7 @@ impl RouterServerHandler {
             })
             .await?;
 
-        // Check that Ollama is running (before validating input)
-        let ollama_running = classifier
-            .model_manager
-            .check_ollama_running()
-            .await
-            .map_err(|_| {
-                r#"{"error":"Could not connect to Ollama. Run init_llm to start it."}"#.to_string()
-            })?;
-
-        if !ollama_running {
-            return Ok(
-                r#"{"error":"Ollama is not running. Run init_llm first to start Ollama and load the model."}"#
-                    .to_string(),
-            );
-        }
-
-        // Check that model is loaded (before validating input)
-        let model_loaded = classifier
-            .model_manager
-            .check_model_loaded()
-            .await
-            .map_err(|_| r#"{"error":"Could not check model status. Ollama may have stopped. Run init_llm again."}"#.to_string())?;
-
-        if !model_loaded {
-            return Ok(
-                r#"{"error":"Model not loaded into memory. Run init_llm to load it."}"#.to_string(),
-            );
-        }
+        let backend_name = classifier.model_manager.backend_name();
 
         // Extract required fields from params
         let task = params
@@ -502,20 +567,77 @@ impl RouterServerHandler {
             .validate()
             .map_err(|e| format!("Input validation failed: {}", e))?;
 
-        // All prerequisites met - perform classification with enhanced metadata
-        let result = classifier.classify_enhanced(&input).await.map_err(|e| {
-            let msg = e.to_string();
-            if msg.contains("connection")
-                || msg.contains("Connection")
-                || msg.contains("error sending request")
-            {
-                "Ollama stopped during classification. Run init_llm to restart it.".to_string()
-            } else {
-                format!("Classification failed: {}", msg)
+        // Hybrid routing checks the backend only when a rule needs LLM tags; a
+        // backend that is down or has no model loaded is an unmet prerequisite
+        let classification_error = |e: anyhow::Error| {
+            if let Some(BackendError::NotReady(message)) = e.downcast_ref() {
+                return Ok(serde_json::json!({ "error": message }).to_string());
             }
-        })?;
+            let msg = e.to_string();
+            Err(
+                if msg.contains("connection")
+                    || msg.contains("Connection")
+                    || msg.contains("error sending request")
+                {
+                    format!(
+                        "{} stopped during classification. Run init_llm to restart it.",
+                        backend_name
+                    )
+                } else {
+                    format!("Classification failed: {}", msg)
+                },
+            )
+        };
+
+        if explain {
+            return match classifier.explain_routing(&input).await {
+                Ok(explanation) => serde_json::to_string(&explanation).map_err(|e| e.to_string()),
+                Err(e) => classification_error(e),
+            };
+        }
 
-        serde_json::to_string(&result).map_err(|e| e.to_string())
+        match classifier.classify_enhanced(&input).await {
+            Ok(result) => serde_json::to_string(&result).map_err(|e| e.to_string()),
+            Err(e) => classification_error(e),
+        }
+    }
+}
+
+impl RouterServerHandler {
+    /// Ask the client for its roots and search them for repository configs. Without
+    /// roots support, discovery starts from the working directory.
+    async fn update_search_roots(&self, runtime: &Arc<dyn McpServer>) {
+        if runtime.client_supports_root_list() != Some(true) {
+            return;
+        }
+        let roots = match runtime.request_root_list(None).await {
+            Ok(result) => result.roots,
+            Err(e) => {
+                warn!("Failed to list client roots: {}", e);
+                return;
+            }
+        };
+        let search_roots: Vec<_> = roots
+            .iter()
+            .filter_map(|root| path_from_file_uri(&root.uri))
+            .collect();
+        info!("Client roots: {:?}", search_roots);
+
+        let classifier = Arc::clone(&self.state.lock().await.classifier);
+
+        // Configs already loaded from the old roots are discovered again. The roots are
+        // only recorded once they load, so a failed reload keeps the previous ones.
+        if let Some(classifier) = classifier.get() {
+            match classifier.set_search_roots(search_roots.clone()) {
+                Ok(diff) if !diff.is_empty() => info!("Configs reloaded: {}", diff),
+                Ok(_) => {}
+                Err(e) => {
+                    warn!("Config reload failed, keeping previous configs: {:#}", e);
+                    return;
+                }
+            }
+        }
+        self.state.lock().await.config.config_search_roots = search_roots;
     }
 }
 
@@ -525,8 +647,97 @@ impl Default for RouterServerHandler {
     }
 }
 
+/// Poll the config files and swap the classifier's configs when they change. The client
+/// is told what changed, or why the new files were rejected, via log notifications.
+async fn watch_configs(
+    classifier: Arc<OnceCell<Classifier>>,
+    runtime: Arc<dyn McpServer>,
+    interval: std::time::Duration,
+) {
+    let mut watcher: Option<ConfigWatcher> = None;
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+        // Not initialized yet: the first request loads the current files anyway
+        let Some(classifier) = classifier.get() else {
+            continue;
+        };
+
+        // The candidate files move when a config appears or the search roots change
+        let paths = classifier.config_paths();
+        if watcher
+            .as_ref()
+            .is_none_or(|w| w.paths() != paths.as_slice())
+        {
+            // Polling only sees changes after this baseline, so reload once to pick up
+            // any made since the configs were last loaded
+            watcher = Some(ConfigWatcher::new(paths));
+        } else if !watcher.as_mut().is_some_and(ConfigWatcher::poll) {
+            continue;
+        }
+
+        let (level, data) = match classifier.reload_configs() {
+            Ok(diff) if diff.is_empty() => continue,
+            Ok(diff) => {
+                info!("Configs reloaded: {}", diff);
+                (
+                    LoggingLevel::Info,
+                    serde_json::json!({
+                        "message": format!("Configs reloaded: {}", diff),
+                        "changes": diff,
+                        "sources": classifier.config_sources(),
+                    }),
+                )
+            }
+            Err(e) => {
+                warn!("Config reload failed, keeping previous configs: {:#}", e);
+                (
+                    LoggingLevel::Error,
+                    serde_json::json!({
+                        "message": format!("Config reload failed, keeping previous configs: {:#}", e),
+                    }),
+                )
+            }
+        };
+
+        let notification = LoggingMessageNotificationParams {
+            data,
+            level,
+            logger: Some("agent-router".to_string()),
+            meta: None,
+        };
+        if let Err(e) = runtime.notify_log_message(notification).await {
+            warn!("Failed to send config reload notification: {}", e);
+        }
+    }
+}
+
 #[async_trait]
 impl rust_mcp_sdk::mcp_server::ServerHandler for RouterServerHandler {
+    async fn on_initialized(&self, runtime: Arc<dyn McpServer>) {
+        self.update_search_roots(&runtime).await;
+
+        let state = self.state.lock().await;
+        if !state.config.watch_configs {
+            return;
+        }
+        let classifier = Arc::clone(&state.classifier);
+        let interval = state.config.config_poll_interval;
+        drop(state);
+
+        tokio::spawn(watch_configs(classifier, runtime, interval));
+    }
+
+    async fn handle_roots_list_changed_notification(
+        &self,
+        _params: Option<NotificationParams>,
+        runtime: Arc<dyn McpServer>,
+    ) -> std::result::Result<(), RpcError> {
+        self.update_search_roots(&runtime).await;
+        Ok(())
+    }
+
     async fn handle_list_tools_request(
         &self,
         _request: Option<PaginatedRequestParams>,
@@ -536,12 +747,24 @@ impl rust_mcp_sdk::mcp_server::ServerHandler for RouterServerHandler {
             tools: vec![
                 Self::create_tool(
                     "init_llm",
-                    "Initialize the LLM: starts Ollama, downloads model if needed, and loads it into memory",
+                    "Initialize the LLM: starts the configured backend (Ollama by default), downloads model if needed, and loads it into memory. For OpenAI-compatible servers only checks the server is reachable",
                 ),
                 Self::create_tool(
                     "get_instructions",
                     "Get routing instructions for which agents should handle a user request",
                 ),
+                Self::create_tool(
+                    "explain_routing",
+                    "Explain a routing decision: runs get_instructions and returns every rule's evaluated conditions, matched files, the raw LLM response and parsed tags, and why referenced agents were dropped",
+                ),
+                Self::create_tool(
+                    "get_config_schema",
+                    "Get the JSON Schema for agents.json, rules.json or llm-tags.json, describing every field and condition type, to help write or fix a config",
+                ),
+                Self::create_tool(
+                    "reload_config",
+                    "Reload agents.json, rules.json and llm-tags.json and report which agents, tags and rules changed. Fails and keeps the current configs if the new files do not validate",
+                ),
             ],
             meta: None,
             next_cursor: None,
@@ -568,7 +791,28 @@ impl rust_mcp_sdk::mcp_server::ServerHandler for RouterServerHandler {
                 .await
                 .map_err(CallToolError::from_message)?,
             "get_instructions" => self
-                .handle_get_instructions_tool(tool_params)
+                .handle_get_instructions_tool(tool_params, false)
+                .await
+                .map_err(CallToolError::from_message)?,
+            "explain_routing" => self
+                .handle_get_instructions_tool(tool_params, true)
+                .await
+                .map_err(CallToolError::from_message)?,
+            "get_config_schema" => {
+                let config = tool_params
+                    .get("config")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| CallToolError::from_message("Missing required field: config"))?;
+                let kind = ConfigKind::parse(config).ok_or_else(|| {
+                    CallToolError::from_message(format!(
+                        "Unknown config '{}', expected agents, rules or tags",
+                        config
+                    ))
+                })?;
+                config_schema(kind).to_string()
+            }
+            "reload_config" => self
+                .handle_reload_config_tool()
                 .await
                 .map_err(CallToolError::from_message)?,
             _ => return Err(CallToolError::unknown_tool(tool_name.clone())),
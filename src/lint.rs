@@ -0,0 +1,495 @@
+// Config linting for the `lint` subcommand.
+//
+// Goes beyond the checks done at load time: every glob and regex is compiled, rules that
+// can never match or duplicate another rule are reported, and the cross-config issues
+// from `validation` are located in the file they concern.
+use crate::extends;
+use crate::rules;
+use crate::types::{Condition, LlmTagConfig, RuleConditions, RulesConfig, UserConfig};
+use crate::validation::{self, ConfigIssue, Severity};
+use std::fmt;
+use std::path::Path;
+
+/// A problem found in a config file, located by a JSON pointer (RFC 6901)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: String,
+    /// JSON pointer into the file, empty for the whole document. For a file that uses
+    /// `extends` it points into the merged config and `file` ends in " (merged)".
+    pub pointer: String,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        if self.pointer.is_empty() {
+            write!(f, "{}: {}: {}", self.file, severity, self.message)
+        } else {
+            write!(
+                f,
+                "{}#{}: {}: {}",
+                self.file, self.pointer, severity, self.message
+            )
+        }
+    }
+}
+
+/// Lint the three config files; `None` stands for the bundled default of that config.
+/// Cross-config checks only run when all of them load.
+pub fn lint_configs(
+    agents_path: Option<&str>,
+    rules_path: Option<&str>,
+    tags_path: Option<&str>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut load_error = |file: &str, e: anyhow::Error| {
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            file: file.to_string(),
+            pointer: String::new(),
+            message: format!("{:#}", e),
+        })
+    };
+
+    let user_config = match agents_path {
+        Some(path) => rules::load_user_config(path),
+        None => rules::default_user_config(),
+    };
+    let tag_config = match tags_path {
+        Some(path) => rules::load_llm_tag_config(path),
+        None => rules::default_llm_tag_config(),
+    };
+    let rules_config = match rules_path {
+        Some(path) => rules::load_rules_config(path),
+        None => rules::default_rules_config(),
+    };
+
+    let agents_path = agents_path.unwrap_or("bundled agents.json");
+    let tags_path = tags_path.unwrap_or("bundled llm-tags.json");
+    let rules_path = rules_path.unwrap_or("bundled rules.json");
+    let user_config = user_config.map_err(|e| load_error(agents_path, e)).ok();
+    let tag_config = tag_config.map_err(|e| load_error(tags_path, e)).ok();
+    let rules_config = rules_config.map_err(|e| load_error(rules_path, e)).ok();
+
+    // Pointers index the config after `extends` is merged, whose lists can differ from
+    // the file's own, so findings in a layered file are labelled as such
+    let merged_label = |path: &str| {
+        if extends::layer_files(Path::new(path)).len() > 1 {
+            format!("{} (merged)", path)
+        } else {
+            path.to_string()
+        }
+    };
+    let agents_path = &merged_label(agents_path);
+    let tags_path = &merged_label(tags_path);
+    let rules_path = &merged_label(rules_path);
+
+    if let Some(ref rules_config) = rules_config {
+        lint_rules(rules_config, rules_path, &mut diagnostics);
+    }
+
+    if let (Some(user_config), Some(tag_config), Some(rules_config)) =
+        (&user_config, &tag_config, &rules_config)
+    {
+        for issue in validation::validate_configs(user_config, tag_config, rules_config) {
+            diagnostics.push(locate_issue(
+                &issue,
+                (agents_path, user_config),
+                (tags_path, tag_config),
+                (rules_path, rules_config),
+            ));
+        }
+    }
+
+    diagnostics
+}
+
+/// Checks that need only the rules config
+fn lint_rules(rules_config: &RulesConfig, file: &str, diagnostics: &mut Vec<Diagnostic>) {
+    for (idx, rule) in rules_config.rules.iter().enumerate() {
+        let pointer = format!("/rules/{}", idx);
+        lint_conditions(
+            &rule.conditions,
+            &format!("{}/conditions", pointer),
+            file,
+            diagnostics,
+        );
+
+        if let Some(first) = rules_config.rules[..idx]
+            .iter()
+            .position(|earlier| earlier.conditions == rule.conditions)
+        {
+            let earlier = &rules_config.rules[first];
+            let message = if earlier.route_to_subagents == rule.route_to_subagents
+                && earlier.suppress_subagents == rule.suppress_subagents
+            {
+                format!("Duplicate of {}", earlier.display_name(first))
+            } else {
+                format!(
+                    "Same conditions as {}; consider merging the rules",
+                    earlier.display_name(first)
+                )
+            };
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                file: file.to_string(),
+                pointer,
+                message,
+            });
+        }
+    }
+}
+
+fn lint_conditions(
+    conditions: &RuleConditions,
+    pointer: &str,
+    file: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut report = |severity, pointer: String, message: String| {
+        diagnostics.push(Diagnostic {
+            severity,
+            file: file.to_string(),
+            pointer,
+            message,
+        })
+    };
+
+    match conditions {
+        RuleConditions::Single(condition) => {
+            let pointer = format!("{}/{}", pointer, condition.kind());
+            if let Some(message) = condition_error(condition) {
+                report(Severity::Error, pointer, message);
+            }
+        }
+        RuleConditions::AnyOf { any_of } => {
+            if any_of.is_empty() {
+                report(
+                    Severity::Error,
+                    format!("{}/any_of", pointer),
+                    "Empty any_of never matches".to_string(),
+                );
+            }
+            for (i, c) in any_of.iter().enumerate() {
+                lint_conditions(c, &format!("{}/any_of/{}", pointer, i), file, diagnostics);
+            }
+        }
+        RuleConditions::AllOf { all_of } => {
+            if all_of.is_empty() {
+                report(
+                    Severity::Error,
+                    format!("{}/all_of", pointer),
+                    "Empty all_of never matches".to_string(),
+                );
+            }
+            // A condition required alongside its own negation can never match
+            for (i, c) in all_of.iter().enumerate() {
+                let excluded: &[RuleConditions] = match c {
+                    RuleConditions::Not { not } => std::slice::from_ref(not.as_ref()),
+                    RuleConditions::NoneOf { none_of } => none_of,
+                    _ => &[],
+                };
+                if excluded.iter().any(|e| all_of.contains(e)) {
+                    report(
+                        Severity::Error,
+                        format!("{}/all_of/{}", pointer, i),
+                        "all_of requires a condition that this branch excludes; the rule can never match"
+                            .to_string(),
+                    );
+                }
+            }
+            for (i, c) in all_of.iter().enumerate() {
+                lint_conditions(c, &format!("{}/all_of/{}", pointer, i), file, diagnostics);
+            }
+        }
+        RuleConditions::Not { not } => {
+            lint_conditions(not, &format!("{}/not", pointer), file, diagnostics);
+        }
+        RuleConditions::NoneOf { none_of } => {
+            if none_of.is_empty() {
+                report(
+                    Severity::Warning,
+                    format!("{}/none_of", pointer),
+                    "Empty none_of always matches".to_string(),
+                );
+            }
+            for (i, c) in none_of.iter().enumerate() {
+                lint_conditions(c, &format!("{}/none_of/{}", pointer, i), file, diagnostics);
+            }
+        }
+    }
+}
+
+/// Why a pattern condition can never match, if it can't
+fn condition_error(condition: &Condition) -> Option<String> {
+    match condition {
+        Condition::FilePattern(pattern) => glob::Pattern::new(pattern)
+            .err()
+            .map(|e| format!("Invalid glob pattern '{}': {}", pattern, e)),
+        Condition::FileRegex(pattern)
+        | Condition::PromptRegex(pattern)
+        | Condition::BranchRegex(pattern) => {
+            if rules::get_compiled_regex(pattern).is_some() {
+                return None;
+            }
+            let detail = regex::Regex::new(pattern)
+                .err()
+                .map(|e| e.to_string())
+                .unwrap_or_default();
+            Some(format!("Invalid regex '{}': {}", pattern, detail))
+        }
+        Condition::LlmTag(_) => None,
+    }
+}
+
+/// Turn a cross-config issue into a diagnostic in the file it concerns
+fn locate_issue(
+    issue: &ConfigIssue,
+    (agents_path, user_config): (&str, &UserConfig),
+    (tags_path, tag_config): (&str, &LlmTagConfig),
+    (rules_path, rules_config): (&str, &RulesConfig),
+) -> Diagnostic {
+    let (file, pointer) = match issue {
+        ConfigIssue::UnknownAgent {
+            rule_index, agent, ..
+        } => {
+            let rule = &rules_config.rules[*rule_index];
+            let pointer = match rule.route_to_subagents.iter().position(|a| a == agent) {
+                Some(i) => format!("/rules/{}/route_to_subagents/{}", rule_index, i),
+                None => {
+                    let i = rule
+                        .suppress_subagents
+                        .iter()
+                        .position(|a| a == agent)
+                        .unwrap_or(0);
+                    format!("/rules/{}/suppress_subagents/{}", rule_index, i)
+                }
+            };
+            (rules_path, pointer)
+        }
+        ConfigIssue::UnknownTag {
+            rule_index,
+            pointer,
+            ..
+        } => (
+            rules_path,
+            format!("/rules/{}/conditions{}", rule_index, pointer),
+        ),
+        ConfigIssue::UnreferencedAgent { agent } => {
+            let i = user_config
+                .agents
+                .iter()
+                .position(|a| &a.name == agent)
+                .unwrap_or(0);
+            (agents_path, format!("/agents/{}", i))
+        }
+        ConfigIssue::UnusedTag { tag } => {
+            let i = tag_config
+                .tags
+                .iter()
+                .position(|t| &t.name == tag)
+                .unwrap_or(0);
+            (tags_path, format!("/tags/{}", i))
+        }
+    };
+
+    Diagnostic {
+        severity: issue.severity(),
+        file: file.to_string(),
+        pointer,
+        message: issue.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const AGENTS: &str = r#"{"agents": [
+        {"name": "rust-reviewer", "description": "Rust"},
+        {"name": "planner", "description": "Planning"}
+    ]}"#;
+    const TAGS: &str = r#"{"tags": [
+        {"name": "security", "description": "Security", "examples": []}
+    ]}"#;
+
+    /// Write the configs to a fresh temp dir and lint them
+    fn lint(name: &str, rules_json: &str) -> Vec<Diagnostic> {
+        let dir =
+            std::env::temp_dir().join(format!("agent-router-lint-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let agents = dir.join("agents.json");
+        let rules = dir.join("rules.json");
+        let tags = dir.join("llm-tags.json");
+        fs::write(&agents, AGENTS).unwrap();
+        fs::write(&rules, rules_json).unwrap();
+        fs::write(&tags, TAGS).unwrap();
+
+        let diagnostics = lint_configs(agents.to_str(), rules.to_str(), tags.to_str());
+        let _ = fs::remove_dir_all(&dir);
+        diagnostics
+    }
+
+    fn locations(diagnostics: &[Diagnostic]) -> Vec<(Severity, &str)> {
+        diagnostics
+            .iter()
+            .map(|d| (d.severity, d.pointer.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_reports_pattern_and_logic_errors() {
+        let diagnostics = lint(
+            "patterns",
+            r#"{"rules": [
+                {"conditions": {"any_of": [{"file_pattern": "src/[*.rs"}, {"branch_regex": "(feat"}]},
+                 "route_to_subagents": ["rust-reviewer"]},
+                {"conditions": {"all_of": [{"llm_tag": "security"}, {"not": {"llm_tag": "security"}}]},
+                 "route_to_subagents": ["rust-reviewer", "ghost"]},
+                {"conditions": {"any_of": [{"file_pattern": "src/[*.rs"}, {"branch_regex": "(feat"}]},
+                 "route_to_subagents": ["planner"]}
+            ]}"#,
+        );
+
+        assert_eq!(
+            locations(&diagnostics),
+            vec![
+                (Severity::Error, "/rules/0/conditions/any_of/0/file_pattern"),
+                (Severity::Error, "/rules/0/conditions/any_of/1/branch_regex"),
+                (Severity::Error, "/rules/1/conditions/all_of/1"),
+                (Severity::Error, "/rules/2/conditions/any_of/0/file_pattern"),
+                (Severity::Error, "/rules/2/conditions/any_of/1/branch_regex"),
+                (Severity::Warning, "/rules/2"),
+                (Severity::Error, "/rules/1/route_to_subagents/1"),
+            ]
+        );
+        assert!(diagnostics[1].message.contains("Invalid regex '(feat'"));
+        assert_eq!(
+            diagnostics[5].message,
+            "Same conditions as Rule #1; consider merging the rules"
+        );
+        assert!(diagnostics[6].file.ends_with("rules.json"));
+        assert!(diagnostics[6]
+            .to_string()
+            .ends_with("rules.json#/rules/1/route_to_subagents/1: error: Rule #2 references agent 'ghost' which is not defined in the agent config"));
+    }
+
+    #[test]
+    fn test_lint_locates_unused_entries_in_their_files() {
+        let diagnostics = lint(
+            "unused",
+            r#"{"rules": [{"conditions": {"file_pattern": "*.rs"}, "route_to_subagents": ["rust-reviewer"]}]}"#,
+        );
+
+        assert_eq!(
+            locations(&diagnostics),
+            vec![
+                (Severity::Warning, "/agents/1"),
+                (Severity::Warning, "/tags/0")
+            ]
+        );
+        assert!(diagnostics[0].file.ends_with("agents.json"));
+        assert!(diagnostics[1].file.ends_with("llm-tags.json"));
+    }
+
+    #[test]
+    fn test_lint_locates_unknown_tags_at_the_condition() {
+        let diagnostics = lint(
+            "unknown-tag",
+            r#"{"rules": [{"conditions": {"all_of": [{"file_pattern": "*.rs"}, {"llm_tag": "ghost"}]},
+                           "route_to_subagents": ["rust-reviewer", "planner"]}]}"#,
+        );
+
+        let errors: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].pointer, "/rules/0/conditions/all_of/1/llm_tag");
+        assert!(errors[0].message.contains("llm_tag 'ghost'"));
+    }
+
+    #[test]
+    fn test_lint_reports_empty_all_of_as_never_matching() {
+        let diagnostics = lint(
+            "empty-all-of",
+            r#"{"rules": [
+                {"conditions": {"file_pattern": "*.rs"}, "route_to_subagents": ["rust-reviewer"]},
+                {"conditions": {"all_of": []}, "route_to_subagents": ["planner"]}
+            ]}"#,
+        );
+
+        let empty = diagnostics
+            .iter()
+            .find(|d| d.pointer == "/rules/1/conditions/all_of")
+            .unwrap();
+        assert_eq!(empty.severity, Severity::Error);
+        assert_eq!(empty.message, "Empty all_of never matches");
+    }
+
+    #[test]
+    fn test_lint_labels_pointers_into_merged_configs() {
+        let dir =
+            std::env::temp_dir().join(format!("agent-router-lint-extends-{}", std::process::id()));
+        fs::create_dir_all(dir.join("base")).unwrap();
+        fs::write(dir.join("agents.json"), AGENTS).unwrap();
+        fs::write(dir.join("llm-tags.json"), TAGS).unwrap();
+        fs::write(
+            dir.join("base/rules.json"),
+            r#"{"rules": [{"id": "rust", "conditions": {"file_pattern": "*.rs"}, "route_to_subagents": ["rust-reviewer"]}]}"#,
+        )
+        .unwrap();
+        let rules = dir.join("rules.json");
+        fs::write(
+            &rules,
+            r#"{"extends": "base/rules.json", "rules": [
+                {"id": "planning", "conditions": {"file_pattern": "[*.md"}, "route_to_subagents": ["planner"]}
+            ]}"#,
+        )
+        .unwrap();
+
+        let diagnostics = lint_configs(
+            dir.join("agents.json").to_str(),
+            rules.to_str(),
+            dir.join("llm-tags.json").to_str(),
+        );
+        let _ = fs::remove_dir_all(&dir);
+
+        // The inherited rule comes first, so the file's own rule 0 is rule 1 once merged
+        let invalid = &diagnostics[0];
+        assert_eq!(invalid.pointer, "/rules/1/conditions/file_pattern");
+        assert_eq!(invalid.file, format!("{} (merged)", rules.display()));
+        assert!(diagnostics[1].file.ends_with("llm-tags.json"));
+    }
+
+    #[test]
+    fn test_lint_reports_load_errors() {
+        let diagnostics = lint("invalid", r#"{"rules": [}"#);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert!(diagnostics[0].pointer.is_empty());
+        assert!(diagnostics[0]
+            .message
+            .contains("Failed to parse rules config"));
+    }
+
+    #[test]
+    fn test_lint_default_configs_have_no_errors() {
+        let diagnostics = lint_configs(
+            Some("./config/agents.json"),
+            Some("./config/rules.json"),
+            Some("./config/llm-tags.json"),
+        );
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+
+        let diagnostics = lint_configs(None, None, None);
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+    }
+}
@@ -1,12 +1,453 @@
-use agent_router_mcp::RouterServerHandler;
+use agent_router_mcp::{
+    config_schema, default_llm_tag_config, default_rules_config, default_user_config, diff_reports,
+    discover, lint_configs, load_dataset, load_llm_tag_config, load_report, load_rule_tests,
+    load_rules_config, load_user_config, run_eval, run_rule_tests, ClassificationInput, Classifier,
+    Config, ConfigKind, ConfigSource, ConfigSources, GitContext, LlmTagConfig, ModelManager,
+    RouterServerHandler, RoutingMode, RulesConfig, Severity, UserConfig,
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use rust_mcp_sdk::error::SdkResult;
 use rust_mcp_sdk::mcp_server::{server_runtime, McpServerOptions};
 use rust_mcp_sdk::schema::*;
 use rust_mcp_sdk::{McpServer, StdioTransport, ToMcpServerHandler, TransportOptions};
+use std::path::PathBuf;
 use tracing::info;
 
+/// Without a subcommand the MCP server runs on stdio
+#[derive(Parser)]
+#[command(name = "agent-router-mcp", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check agents.json, rules.json and llm-tags.json; exits non-zero on errors
+    Lint(LintArgs),
+    /// Route a request offline and print the get_instructions JSON response
+    Route(RouteArgs),
+    /// Run rule test fixtures against the configs; exits non-zero on failures
+    Test(TestArgs),
+    /// Measure LLM tagging accuracy on a labelled JSONL dataset
+    Eval(EvalArgs),
+    /// Print the configs the server would load from here, with `extends` merged
+    DumpConfig(DumpConfigArgs),
+    /// Print the JSON Schema for a config file
+    Schema(SchemaArgs),
+}
+
+/// Config file locations. Each defaults to the config the server would discover from
+/// the working directory, ending with the bundled defaults.
+#[derive(Args)]
+struct ConfigPaths {
+    /// Agent definitions (default: the discovered config)
+    #[arg(long)]
+    agents: Option<String>,
+    /// Routing rules (default: the discovered config)
+    #[arg(long)]
+    rules: Option<String>,
+    /// LLM tag definitions (default: the discovered config)
+    #[arg(long)]
+    tags: Option<String>,
+}
+
+impl ConfigPaths {
+    fn agents(&self) -> Option<String> {
+        config_path(&self.agents, ConfigKind::Agents)
+    }
+
+    fn rules(&self) -> Option<String> {
+        config_path(&self.rules, ConfigKind::Rules)
+    }
+
+    fn tags(&self) -> Option<String> {
+        config_path(&self.tags, ConfigKind::LlmTags)
+    }
+
+    /// Sources for `Classifier::initialize_from`; only configs without a flag are discovered
+    fn sources(&self) -> ConfigSources {
+        let source = |flag: &Option<String>, kind: ConfigKind| match flag {
+            Some(path) => ConfigSource::CommandLine(PathBuf::from(path)),
+            None => discover(kind, &[]),
+        };
+        ConfigSources {
+            agents: source(&self.agents, ConfigKind::Agents),
+            llm_tags: source(&self.tags, ConfigKind::LlmTags),
+            rules: source(&self.rules, ConfigKind::Rules),
+        }
+    }
+
+    fn load(&self) -> anyhow::Result<(UserConfig, LlmTagConfig, RulesConfig)> {
+        let user_config = match self.agents() {
+            Some(path) => load_user_config(&path)?,
+            None => default_user_config()?,
+        };
+        let tag_config = match self.tags() {
+            Some(path) => load_llm_tag_config(&path)?,
+            None => default_llm_tag_config()?,
+        };
+        let rules_config = match self.rules() {
+            Some(path) => load_rules_config(&path)?,
+            None => default_rules_config()?,
+        };
+        Ok((user_config, tag_config, rules_config))
+    }
+}
+
+/// The file named on the command line, else the one discovery picks. `None` means the
+/// bundled defaults.
+fn config_path(flag: &Option<String>, kind: ConfigKind) -> Option<String> {
+    flag.clone().or_else(|| {
+        discover(kind, &[])
+            .path()
+            .map(|path| path.to_string_lossy().into_owned())
+    })
+}
+
+#[derive(Args)]
+struct LintArgs {
+    #[command(flatten)]
+    paths: ConfigPaths,
+    /// Exit non-zero on warnings too
+    #[arg(long)]
+    deny_warnings: bool,
+}
+
+#[derive(Args)]
+struct RouteArgs {
+    /// What the agent is doing
+    #[arg(long)]
+    task: String,
+    /// The agent's intent (e.g. "review code before commit")
+    #[arg(long)]
+    intent: String,
+    /// The original user request
+    #[arg(long)]
+    prompt: Option<String>,
+    /// A file associated with the task (repeatable)
+    #[arg(long = "file", value_name = "PATH")]
+    files: Vec<String>,
+    /// Git branch for branch_regex conditions
+    #[arg(long)]
+    branch: Option<String>,
+    #[command(flatten)]
+    paths: ConfigPaths,
+    /// Evaluate deterministic conditions only; llm_tag conditions never match
+    #[arg(long)]
+    no_llm: bool,
+}
+
+#[derive(Args)]
+struct TestArgs {
+    #[command(flatten)]
+    paths: ConfigPaths,
+    /// Fixture files, or directories whose *.json files are fixtures
+    #[arg(required = true, value_name = "FIXTURE")]
+    fixtures: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+struct EvalArgs {
+    /// JSONL file, one {"task", "intent", "expected_tags", ...} object per line
+    dataset: PathBuf,
+    /// LLM tag definitions (default: the discovered config)
+    #[arg(long)]
+    tags: Option<String>,
+    /// Write the report as JSON, for use as a later --baseline
+    #[arg(long, value_name = "PATH")]
+    save: Option<PathBuf>,
+    /// Report saved by an earlier run to compare against
+    #[arg(long, value_name = "PATH")]
+    baseline: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct DumpConfigArgs {
+    /// Print only this config, in its file format
+    #[arg(value_enum)]
+    config: Option<ConfigName>,
+}
+
+#[derive(Args)]
+struct SchemaArgs {
+    #[arg(value_enum)]
+    config: ConfigName,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ConfigName {
+    Agents,
+    Rules,
+    Tags,
+}
+
+impl ConfigName {
+    fn kind(self) -> ConfigKind {
+        match self {
+            ConfigName::Agents => ConfigKind::Agents,
+            ConfigName::Rules => ConfigKind::Rules,
+            ConfigName::Tags => ConfigKind::LlmTags,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> SdkResult<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        None => run_server().await,
+        Some(Command::Lint(args)) => std::process::exit(run_lint(&args)),
+        Some(Command::Route(args)) => std::process::exit(run_route(args).await),
+        Some(Command::Test(args)) => std::process::exit(run_tests(&args)),
+        Some(Command::Eval(args)) => std::process::exit(run_eval_command(&args).await),
+        Some(Command::DumpConfig(args)) => std::process::exit(run_dump_config(&args)),
+        Some(Command::Schema(args)) => {
+            let schema = config_schema(args.config.kind());
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&schema).unwrap_or_default()
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Print lint diagnostics to stdout and return the process exit code
+fn run_lint(args: &LintArgs) -> i32 {
+    let diagnostics = lint_configs(
+        args.paths.agents().as_deref(),
+        args.paths.rules().as_deref(),
+        args.paths.tags().as_deref(),
+    );
+
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    println!("{} error(s), {} warning(s)", errors, warnings);
+
+    if errors > 0 || (args.deny_warnings && warnings > 0) {
+        1
+    } else {
+        0
+    }
+}
+
+/// Classify one request with `Classifier::classify_enhanced`, print the response as JSON
+/// to stdout and return the process exit code
+async fn run_route(args: RouteArgs) -> i32 {
+    init_cli_logging();
+
+    let mut config = Config::default();
+    if args.no_llm {
+        config.routing_mode = RoutingMode::RulesOnly;
+    }
+
+    let input = ClassificationInput {
+        task: args.task,
+        intent: args.intent,
+        original_prompt: args.prompt,
+        associated_files: (!args.files.is_empty()).then_some(args.files),
+        git_context: args.branch.map(|branch| GitContext {
+            branch,
+            changed_files: vec![],
+            staged_files: vec![],
+            tag: None,
+        }),
+        agent_config_path: None,
+        rules_config_path: None,
+        llm_tags_path: None,
+    };
+    let sources = args.paths.sources();
+
+    let result = async {
+        let mut classifier = Classifier::new(config)?;
+        classifier.initialize_from(sources).await?;
+        let response = classifier.classify_enhanced(&input).await?;
+        Ok::<_, anyhow::Error>(serde_json::to_string_pretty(&response)?)
+    }
+    .await;
+
+    match result {
+        Ok(json) => {
+            println!("{}", json);
+            0
+        }
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            1
+        }
+    }
+}
+
+/// Run every fixture, print a PASS/FAIL line per case with the mismatches of failed
+/// cases, and return the process exit code
+fn run_tests(args: &TestArgs) -> i32 {
+    let (user_config, tag_config, rules_config) = match args.paths.load() {
+        Ok(configs) => configs,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            return 1;
+        }
+    };
+
+    let mut files = Vec::new();
+    for path in &args.fixtures {
+        if path.is_dir() {
+            let entries = match std::fs::read_dir(path) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    eprintln!("error: Failed to read {}: {}", path.display(), e);
+                    return 1;
+                }
+            };
+            let mut dir_files: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("json"))
+                .collect();
+            dir_files.sort();
+            files.extend(dir_files);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let (mut passed, mut failed) = (0, 0);
+    for file in &files {
+        println!("{}", file.display());
+        let results = load_rule_tests(file)
+            .and_then(|suite| run_rule_tests(&suite, &user_config, &tag_config, &rules_config));
+        let results = match results {
+            Ok(results) => results,
+            Err(e) => {
+                println!("  ERROR {:#}", e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        for result in results {
+            if result.passed() {
+                println!("  PASS {}", result.name);
+                passed += 1;
+                continue;
+            }
+            println!("  FAIL {}", result.name);
+            for mismatch in &result.mismatches {
+                println!("    {}", mismatch);
+            }
+            let routed: Vec<String> = result
+                .routed
+                .iter()
+                .map(|(agent, confidence)| format!("{} ({})", agent, confidence))
+                .collect();
+            if routed.is_empty() {
+                println!("    routed: (none)");
+            } else {
+                println!("    routed: {}", routed.join(", "));
+            }
+            failed += 1;
+        }
+    }
+
+    println!("{} passed, {} failed", passed, failed);
+    if failed > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Tag every dataset case with the configured backend, print the report (and the change
+/// against a baseline) and return the process exit code
+async fn run_eval_command(args: &EvalArgs) -> i32 {
+    init_cli_logging();
+
+    let result = async {
+        let tag_config = match config_path(&args.tags, ConfigKind::LlmTags) {
+            Some(path) => load_llm_tag_config(&path)?,
+            None => default_llm_tag_config()?,
+        };
+        let cases = load_dataset(&args.dataset)?;
+        let baseline = args.baseline.as_deref().map(load_report).transpose()?;
+
+        let mut model_manager = ModelManager::new(Config::default())?;
+        model_manager.initialize().await?;
+        let report = run_eval(&model_manager, &tag_config, &cases).await;
+
+        println!("{}", report);
+        if let Some(baseline) = baseline {
+            println!("\n{}", diff_reports(&baseline, &report));
+        }
+        if let Some(ref path) = args.save {
+            std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+            println!("\nReport saved to {}", path.display());
+        }
+        Ok::<_, anyhow::Error>(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            1
+        }
+    }
+}
+
+/// Discover and load the configs like the server, print them as JSON and return the
+/// process exit code
+fn run_dump_config(args: &DumpConfigArgs) -> i32 {
+    init_cli_logging();
+
+    let result =
+        Classifier::load_configs(Config::default().strict_config, &[]).and_then(|configs| {
+            let value = match args.config {
+                None => serde_json::json!({
+                    "sources": configs.sources,
+                    "agents": &configs.user_config.agents,
+                    "tags": &configs.tag_config.tags,
+                    "rules": &configs.rules_config.rules,
+                }),
+                Some(ConfigName::Agents) => serde_json::to_value(&*configs.user_config)?,
+                Some(ConfigName::Rules) => serde_json::to_value(&*configs.rules_config)?,
+                Some(ConfigName::Tags) => serde_json::to_value(&*configs.tag_config)?,
+            };
+            Ok(serde_json::to_string_pretty(&value)?)
+        });
+
+    match result {
+        Ok(json) => {
+            println!("{}", json);
+            0
+        }
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            1
+        }
+    }
+}
+
+/// Errors only, to stderr, so a subcommand's stdout stays clean (valid JSON for `route`)
+fn init_cli_logging() {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter("agent_router_mcp=error")
+        .with_ansi(false)
+        .without_time()
+        .with_target(false)
+        .init();
+}
+
+async fn run_server() -> SdkResult<()> {
     // CRITICAL: Initialize logging to stderr only (not stdout)
     // Writing to stdout corrupts JSON-RPC messages
     tracing_subscriber::fmt()
@@ -33,7 +474,12 @@ async fn main() -> SdkResult<()> {
             website_url: Some("https://github.com/yourusername/agent-router-mcp".into()),
         },
         capabilities: ServerCapabilities {
-            tools: Some(ServerCapabilitiesTools { list_changed: None }),
+            // The tool list and schemas are fixed; config reloads never change them
+            tools: Some(ServerCapabilitiesTools {
+                list_changed: Some(false),
+            }),
+            // Config reloads are reported as log notifications
+            logging: Some(serde_json::Map::new()),
             ..Default::default()
         },
         protocol_version: ProtocolVersion::V2025_11_25.into(),
@@ -1,50 +1,36 @@
+use crate::backend::{self, BackendError, GenerateRequest, LlmBackend};
 use crate::types::*;
 use anyhow::{Context, Result};
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use std::process::{Command, Stdio};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
-use tokio::io::{AsyncBufReadExt, BufReader};
 use tracing::{info, warn};
 
-#[derive(Serialize)]
-struct OllamaRequest {
-    model: String,
-    prompt: String,
-    stream: bool,
-    options: OllamaOptions,
-    /// Enable thinking/reasoning mode for supported models
-    #[serde(skip_serializing_if = "Option::is_none")]
-    think: Option<bool>,
-}
-
-#[derive(Serialize)]
-struct OllamaOptions {
-    temperature: f32,
-    num_predict: i32,
-}
+/// Confidence assigned to tags from the legacy numbered-list prompt, which cannot
+/// express how sure the model is
+const LEGACY_TAG_CONFIDENCE: u8 = 85;
 
-#[derive(Deserialize)]
-struct OllamaResponse {
-    response: String,
-    /// Thinking/reasoning trace from models that support it
-    #[serde(default)]
-    thinking: Option<String>,
+pub struct ModelManager {
+    backend: Box<dyn LlmBackend>,
+    config: Config,
+    /// Cleared once the backend rejects a `format` schema, so later requests go
+    /// straight to the legacy prompt instead of failing first
+    structured_output_supported: AtomicBool,
 }
 
-#[derive(Deserialize)]
-struct OllamaModelsResponse {
-    models: Vec<OllamaModel>,
+/// Structured tagging reply: `{"tags": [{"name": "...", "confidence": 0-100}]}`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TagResponse {
+    tags: Vec<TagEntry>,
 }
 
-#[derive(Deserialize)]
-struct OllamaModel {
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TagEntry {
     name: String,
-}
-
-pub struct ModelManager {
-    client: Client,
-    config: Config,
+    confidence: i64,
 }
 
 impl ModelManager {
@@ -57,53 +43,59 @@ impl ModelManager {
             .build()
             .context("Failed to create HTTP client - TLS backend initialization failed. This may indicate missing CA certificates or a corrupted TLS installation.")?;
 
-        Ok(Self { client, config })
+        let backend = backend::create_backend(&config, client);
+        Ok(Self::with_backend(config, backend))
+    }
+
+    /// Create a model manager around an already constructed backend
+    pub fn with_backend(config: Config, backend: Box<dyn LlmBackend>) -> Self {
+        Self {
+            backend,
+            config,
+            structured_output_supported: AtomicBool::new(true),
+        }
     }
 
-    pub fn check_ollama_installed(&self) -> Result<bool> {
-        let result = std::process::Command::new("ollama")
-            .arg("--version")
-            .output();
+    /// Human-readable name of the configured backend (e.g. "Ollama")
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
 
-        Ok(result.is_ok())
+    /// Message to show when the configured backend is not installed
+    pub fn backend_not_installed_message(&self) -> String {
+        self.backend.not_installed_message()
     }
 
-    pub async fn check_model_name_valid(&self, model_name: &str) -> Result<bool> {
-        // Check if model name exists in Ollama's library by attempting to show it
-        // The 'ollama show' command will succeed if the model exists in the library
-        // (even if not downloaded), and fail if it doesn't exist
-        if model_name.is_empty() {
-            return Ok(false);
-        }
+    /// Whether the backend downloads and loads models itself (false for servers the user runs)
+    pub fn backend_manages_models(&self) -> bool {
+        self.backend.manages_models()
+    }
 
-        // Use 'ollama show' to verify the model exists in Ollama's library
-        let output = tokio::process::Command::new("ollama")
-            .args(["show", model_name, "--modelfile"])
-            .output()
-            .await
-            .context("Failed to execute 'ollama show' command")?;
+    pub fn check_backend_installed(&self) -> Result<bool> {
+        self.backend.check_installed()
+    }
 
-        // If the command succeeds, the model is valid in Ollama's library
-        // If it fails with "model not found" or similar, the model doesn't exist
-        Ok(output.status.success())
+    /// Whether the backend's model library knows the name; `None` when it cannot tell
+    pub async fn check_model_name_valid(&self, model_name: &str) -> Result<Option<bool>> {
+        self.backend.check_model_name_valid(model_name).await
     }
 
     pub async fn initialize(&mut self) -> Result<()> {
         info!("Initializing model manager...");
 
-        if !self.check_ollama_running().await? {
+        if !self.check_backend_running().await? {
             anyhow::bail!(
-                "Ollama not running. Start it with:\n  ollama serve\n\n\
-                 Or install it from: https://ollama.com"
+                "{} not running. Run the init_llm tool to start it.",
+                self.backend.name()
             );
         }
 
-        if !self.check_model_exists().await? {
+        // Servers the user runs serve whatever model they were started with
+        if self.backend.manages_models() && !self.check_model_exists().await? {
             anyhow::bail!(
-                "Model '{}' not found. Download it with:\n  ollama pull {}\n\n\
-                 The model will be downloaded automatically on first use.",
+                "Model '{}' not found in {}. Run the init_llm tool to download it.",
                 self.config.model_name,
-                self.config.model_name
+                self.backend.name()
             );
         }
 
@@ -111,175 +103,259 @@ impl ModelManager {
         Ok(())
     }
 
-    /// Step 1: LLM identifies semantic tags
+    /// Step 1: LLM identifies semantic tags, each with a confidence (0-100).
+    /// Asks for schema-constrained JSON when enabled, falling back to the legacy
+    /// numbered-list prompt if the backend or model does not support structured output.
+    /// Tags scoring below their `min_confidence` are dropped.
     pub async fn identify_tags(
         &self,
         input: &ClassificationInput,
         tag_config: &LlmTagConfig,
-    ) -> Result<Vec<String>> {
-        let prompt = self.build_tagging_prompt(input, tag_config)?;
+    ) -> Result<Vec<TagResult>> {
+        Ok(self.identify_tags_with_raw(input, tag_config).await?.0)
+    }
 
-        // Enable thinking mode if configured and model supports it
-        let use_thinking = self.config.should_use_thinking();
-        if use_thinking {
-            info!("Thinking mode enabled for tagging");
+    /// Like `identify_tags`, but also returns the raw reply text of every request made
+    /// (a rejected structured reply followed by the legacy retry yields two entries)
+    pub async fn identify_tags_with_raw(
+        &self,
+        input: &ClassificationInput,
+        tag_config: &LlmTagConfig,
+    ) -> Result<(Vec<TagResult>, Vec<String>)> {
+        let mut raw_responses = Vec::new();
+        let mut scored = None;
+        if self.config.structured_output && self.structured_output_supported.load(Ordering::Relaxed)
+        {
+            scored = self
+                .identify_tags_structured(input, tag_config, &mut raw_responses)
+                .await?;
         }
 
-        // Use configured temperature or default to 0.1 for tagging (more deterministic)
-        let temperature = self.config.temperature.unwrap_or(0.1);
+        let scored = match scored {
+            Some(scored) => scored,
+            None => self
+                .identify_tags_legacy(input, tag_config, &mut raw_responses)
+                .await?
+                .into_iter()
+                .map(|tag| (tag, LEGACY_TAG_CONFIDENCE))
+                .collect(),
+        };
 
-        let request = OllamaRequest {
-            model: self.config.effective_model_name(),
-            prompt,
-            stream: false,
-            options: OllamaOptions {
-                temperature,
-                num_predict: if use_thinking { 500 } else { 100 }, // More tokens for thinking
-            },
-            think: if use_thinking { Some(true) } else { None },
+        // The LLM tags the request as a whole, so each tag covers all associated files
+        let files = input.associated_files.clone().unwrap_or_default();
+        let tags = Self::apply_min_confidence(scored, tag_config)
+            .into_iter()
+            .map(|(tag, confidence)| TagResult {
+                tag,
+                confidence,
+                files: files.clone(),
+            })
+            .collect();
+        Ok((tags, raw_responses))
+    }
+
+    /// Drop tags whose confidence is zero or below the tag's `min_confidence`
+    pub(crate) fn apply_min_confidence(
+        scored: Vec<(String, u8)>,
+        tag_config: &LlmTagConfig,
+    ) -> Vec<(String, u8)> {
+        scored
+            .into_iter()
+            .filter(|(tag, confidence)| {
+                let min_confidence = tag_config
+                    .tags
+                    .iter()
+                    .find(|t| &t.name == tag)
+                    .and_then(|t| t.min_confidence)
+                    .unwrap_or(0);
+
+                let keep = *confidence > 0 && *confidence >= min_confidence;
+                if !keep {
+                    info!(
+                        "Dropping tag '{}': confidence {} below minimum {}",
+                        tag, confidence, min_confidence
+                    );
+                }
+                keep
+            })
+            .collect()
+    }
+
+    /// Structured tagging. Returns `None` when the reply cannot be used and the
+    /// legacy prompt should be tried instead.
+    async fn identify_tags_structured(
+        &self,
+        input: &ClassificationInput,
+        tag_config: &LlmTagConfig,
+        raw_responses: &mut Vec<String>,
+    ) -> Result<Option<Vec<(String, u8)>>> {
+        let prompt = self.build_structured_tagging_prompt(input, tag_config);
+        let request = self.tagging_request(prompt, Some(Self::tag_response_schema(tag_config)));
+
+        let data = match self.backend.generate(&request).await {
+            Ok(data) => data,
+            Err(e) => {
+                if let Some(BackendError::StructuredOutputUnsupported { .. }) = e.downcast_ref() {
+                    warn!("{:#}; using legacy tag parsing", e);
+                    self.structured_output_supported
+                        .store(false, Ordering::Relaxed);
+                    return Ok(None);
+                }
+                return Err(e.context("Tagging request failed"));
+            }
         };
 
-        let response = self
-            .client
-            .post(format!("{}/api/generate", self.config.ollama_url))
-            .json(&request)
-            .send()
-            .await
-            .context("Failed to send tagging request to Ollama")?;
+        // Log thinking trace if available (helps with debugging)
+        if let Some(ref thinking) = data.thinking {
+            info!("LLM thinking trace: {:?}", thinking);
+        }
 
-        if !response.status().is_success() {
-            anyhow::bail!("Ollama tagging request failed: {}", response.status());
+        info!("LLM raw structured tagging response: {:?}", data.text);
+        raw_responses.push(data.text.clone());
+        match Self::parse_structured_tags(&data.text, tag_config) {
+            Ok(entries) => {
+                // Confidence was range-checked by parse_structured_tags
+                let tags: Vec<(String, u8)> = entries
+                    .into_iter()
+                    .map(|e| (e.name, e.confidence as u8))
+                    .collect();
+                info!("Parsed tags: {:?}", tags);
+                Ok(Some(tags))
+            }
+            Err(reason) => {
+                // The model ignored the schema; its free text is not safe to scrape
+                warn!(
+                    "Structured tagging response rejected ({}); retrying with legacy prompt",
+                    reason
+                );
+                Ok(None)
+            }
         }
+    }
 
-        let data: OllamaResponse = response
-            .json()
+    /// Legacy tagging: the model replies with tag numbers, scraped by `parse_tag_list`
+    async fn identify_tags_legacy(
+        &self,
+        input: &ClassificationInput,
+        tag_config: &LlmTagConfig,
+        raw_responses: &mut Vec<String>,
+    ) -> Result<Vec<String>> {
+        let prompt = self.build_tagging_prompt(input, tag_config)?;
+        let request = self.tagging_request(prompt, None);
+
+        let data = self
+            .backend
+            .generate(&request)
             .await
-            .context("Failed to parse Ollama tagging response")?;
+            .context("Tagging request failed")?;
 
         // Log thinking trace if available (helps with debugging)
         if let Some(ref thinking) = data.thinking {
             info!("LLM thinking trace: {:?}", thinking);
         }
 
-        info!("LLM raw tagging response: {:?}", data.response);
+        info!("LLM raw tagging response: {:?}", data.text);
+        raw_responses.push(data.text.clone());
         info!("Tag config has {} tags", tag_config.tags.len());
-        let tags = self.parse_tag_list(&data.response, tag_config);
+        let tags = self.parse_tag_list(&data.text, tag_config);
         info!("Parsed tags: {:?}", tags);
         Ok(tags)
     }
 
-    pub async fn check_ollama_running(&self) -> Result<bool> {
-        match self
-            .client
-            .get(format!("{}/api/tags", self.config.ollama_url))
-            .send()
-            .await
-        {
-            Ok(resp) => Ok(resp.status().is_success()),
-            Err(_) => Ok(false),
+    fn tagging_request(
+        &self,
+        prompt: String,
+        format: Option<serde_json::Value>,
+    ) -> GenerateRequest {
+        // Enable thinking mode if configured and model supports it
+        let use_thinking = self.config.should_use_thinking();
+        if use_thinking {
+            info!("Thinking mode enabled for tagging");
         }
-    }
-
-    pub async fn check_model_exists(&self) -> Result<bool> {
-        let response = self
-            .client
-            .get(format!("{}/api/tags", self.config.ollama_url))
-            .send()
-            .await?;
 
-        let data: OllamaModelsResponse = response.json().await?;
+        // Use configured temperature or default to 0.1 for tagging (more deterministic)
+        let temperature = self.config.temperature.unwrap_or(0.1);
 
-        let effective_name = self.config.effective_model_name();
-        let model_base = effective_name.split(':').next().unwrap_or("");
+        // JSON replies are longer than a list of numbers; thinking needs more on top
+        let max_tokens = match (format.is_some(), use_thinking) {
+            (true, true) => 800,
+            (true, false) => 300,
+            (false, true) => 500,
+            (false, false) => 100,
+        };
 
-        Ok(data
-            .models
-            .iter()
-            .any(|m| m.name == effective_name || m.name.starts_with(model_base)))
+        GenerateRequest {
+            model: self.config.effective_model_name(),
+            prompt,
+            temperature,
+            max_tokens,
+            think: use_thinking,
+            format,
+        }
     }
 
-    pub async fn check_model_loaded(&self) -> Result<bool> {
-        let response = self
-            .client
-            .get(format!("{}/api/ps", self.config.ollama_url))
-            .send()
-            .await?;
-
-        #[derive(Deserialize)]
-        struct RunningModelsResponse {
-            models: Vec<RunningModel>,
-        }
+    pub async fn check_backend_running(&self) -> Result<bool> {
+        self.backend.is_running().await
+    }
 
-        #[derive(Deserialize)]
-        struct RunningModel {
-            name: String,
-        }
+    /// Start the backend server. Returns true once it is ready to accept requests.
+    pub async fn start_backend(&self) -> Result<bool> {
+        self.backend.start().await
+    }
 
-        let data: RunningModelsResponse = response.json().await?;
+    pub async fn check_model_exists(&self) -> Result<bool> {
+        let models = self.backend.list_models().await?;
         let effective_name = self.config.effective_model_name();
-        let model_base = effective_name.split(':').next().unwrap_or("");
 
-        Ok(data
-            .models
+        Ok(models
             .iter()
-            .any(|m| m.name == effective_name || m.name.starts_with(model_base)))
+            .any(|m| backend::model_matches(m, &effective_name)))
     }
 
-    pub async fn load_model(&self) -> Result<()> {
-        info!("Loading model...");
-
-        let request = OllamaRequest {
-            model: self.config.effective_model_name(),
-            prompt: "".to_string(),
-            stream: false,
-            options: OllamaOptions {
-                temperature: 0.0,
-                num_predict: 1,
-            },
-            think: None, // No thinking needed for model loading
-        };
-
-        let response = self
-            .client
-            .post(format!("{}/api/generate", self.config.ollama_url))
-            .json(&request)
-            .send()
+    pub async fn check_model_loaded(&self) -> Result<bool> {
+        self.backend
+            .is_loaded(&self.config.effective_model_name())
             .await
-            .context("Failed to load model")?;
-
-        if !response.status().is_success() {
-            // 404 means the model isn't installed
-            if response.status() == 404 {
-                anyhow::bail!(
-                    "Model '{}' is not installed. Please pull it first using the pull_model tool.",
-                    self.config.model_name
-                );
-            }
-            anyhow::bail!("Failed to load model: {}", response.status());
-        }
-
-        info!("Model ready");
-        Ok(())
     }
 
-    pub fn start_ollama(&self) -> Result<()> {
-        info!("Starting Ollama service...");
-
-        let result = Command::new("ollama")
-            .arg("serve")
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn();
+    /// Check that the backend is running with the model loaded, failing with a
+    /// `BackendError::NotReady` that tells the user to run `init_llm`
+    pub async fn ensure_ready(&self) -> Result<()> {
+        let name = self.backend_name();
+        let not_ready = |message: String| Err(BackendError::NotReady(message).into());
+
+        match self.check_backend_running().await {
+            Ok(true) => {}
+            Ok(false) => {
+                return not_ready(format!(
+                    "{} is not running. Run init_llm first to start {} and load the model.",
+                    name, name
+                ))
+            }
+            Err(_) => {
+                return not_ready(format!(
+                    "Could not connect to {}. Run init_llm to start it.",
+                    name
+                ))
+            }
+        }
 
-        match result {
-            Ok(_) => Ok(()),
-            Err(e) => {
-                warn!("Could not start Ollama (it may already be running): {}", e);
-                Ok(())
+        match self.check_model_loaded().await {
+            Ok(true) => Ok(()),
+            Ok(false) => {
+                not_ready("Model not loaded into memory. Run init_llm to load it.".to_string())
             }
+            Err(_) => not_ready(format!(
+                "Could not check model status. {} may have stopped. Run init_llm again.",
+                name
+            )),
         }
     }
 
+    pub async fn load_model(&self) -> Result<()> {
+        self.backend.load(&self.config.effective_model_name()).await
+    }
+
     /// Pull a model with progress reporting via callback.
     /// The callback receives the current percentage (0-100).
     pub async fn pull_model_with_progress<F>(
@@ -290,76 +366,7 @@ impl ModelManager {
     where
         F: FnMut(u8) + Send,
     {
-        info!("Pulling model...");
-
-        let mut child = tokio::process::Command::new("ollama")
-            .args(["pull", model_name])
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped())
-            .spawn()
-            .context("Failed to execute 'ollama pull' command")?;
-
-        // Ollama writes progress to stderr, not stdout
-        let stderr = child.stderr.take().expect("stderr was piped");
-        let mut reader = BufReader::new(stderr).lines();
-
-        let mut last_percent: u8 = 0;
-        let mut last_error_line = String::new();
-
-        while let Some(line) = reader.next_line().await? {
-            // Look for percentage pattern (e.g., "50%" or "pulling 50%")
-            if let Some(percent) = Self::parse_percentage(&line) {
-                if percent > last_percent {
-                    last_percent = percent;
-                    on_progress(percent);
-                }
-            }
-            // Keep track of last line for error reporting
-            last_error_line = line;
-        }
-
-        let status = child.wait().await?;
-
-        if !status.success() {
-            // Provide source-appropriate error messages
-            let browse_url = match self.config.model_source {
-                ModelSource::HuggingFace => "https://huggingface.co/models?library=gguf",
-                ModelSource::Ollama => "https://ollama.com/library",
-            };
-
-            let error_detail = if last_error_line.is_empty() {
-                String::new()
-            } else {
-                format!("\nError: {}", last_error_line)
-            };
-
-            anyhow::bail!(
-                "Failed to pull model '{}'. Please verify the model name is correct.{}\n\
-                 Browse available models at: {}",
-                model_name,
-                error_detail,
-                browse_url
-            );
-        }
-
-        // Send 100% completion
-        on_progress(100);
-        info!("Model pulled successfully");
-        Ok(())
-    }
-
-    /// Parse percentage from Ollama output line.
-    /// Ollama outputs progress like "pulling abc123... 45%" or just contains percentage.
-    fn parse_percentage(line: &str) -> Option<u8> {
-        // Look for pattern like "45%" in the line
-        for word in line.split_whitespace() {
-            if word.ends_with('%') {
-                if let Ok(num) = word.trim_end_matches('%').parse::<u8>() {
-                    return Some(num.min(100));
-                }
-            }
-        }
-        None
+        self.backend.pull(model_name, &mut on_progress).await
     }
 
     pub async fn pull_model(&self, model_name: &str) -> Result<()> {
@@ -378,11 +385,8 @@ impl ModelManager {
             .join(" ")
     }
 
-    fn build_tagging_prompt(
-        &self,
-        input: &ClassificationInput,
-        tag_config: &LlmTagConfig,
-    ) -> Result<String> {
+    /// Task/intent/files section shared by the tagging prompts (inputs sanitized)
+    fn build_task_context(input: &ClassificationInput) -> String {
         // Security: Sanitize user inputs (normalize whitespace)
         let sanitized_task = Self::sanitize_input(&input.task);
         let sanitized_intent = Self::sanitize_input(&input.intent);
@@ -416,6 +420,32 @@ impl ModelManager {
                 .unwrap_or_else(|| "none".to_string())
         };
 
+        // Include original_prompt if provided (for better context)
+        if let Some(ref original) = sanitized_original_prompt {
+            format!(
+                r#"Task: "{}"
+Intent: "{}"
+Original request: "{}"
+Changed files: {}"#,
+                sanitized_task, sanitized_intent, original, changed_files
+            )
+        } else {
+            format!(
+                r#"Task: "{}"
+Intent: "{}"
+Changed files: {}"#,
+                sanitized_task, sanitized_intent, changed_files
+            )
+        }
+    }
+
+    fn build_tagging_prompt(
+        &self,
+        input: &ClassificationInput,
+        tag_config: &LlmTagConfig,
+    ) -> Result<String> {
+        let prompt_context = Self::build_task_context(input);
+
         let tag_list = tag_config
             .tags
             .iter()
@@ -437,24 +467,6 @@ impl ModelManager {
             .collect::<Vec<_>>()
             .join("\n");
 
-        // Include original_prompt if provided (for better context)
-        let prompt_context = if let Some(ref original) = sanitized_original_prompt {
-            format!(
-                r#"Task: "{}"
-Intent: "{}"
-Original request: "{}"
-Changed files: {}"#,
-                sanitized_task, sanitized_intent, original, changed_files
-            )
-        } else {
-            format!(
-                r#"Task: "{}"
-Intent: "{}"
-Changed files: {}"#,
-                sanitized_task, sanitized_intent, changed_files
-            )
-        };
-
         Ok(format!(
             r#"You are a code task classifier. Be CONSERVATIVE - only select tags that CLEARLY match.
 
@@ -472,6 +484,101 @@ Reply with the number(s) only, comma-separated. Reply "0" if none apply."#,
         ))
     }
 
+    fn build_structured_tagging_prompt(
+        &self,
+        input: &ClassificationInput,
+        tag_config: &LlmTagConfig,
+    ) -> String {
+        let prompt_context = Self::build_task_context(input);
+
+        let tag_list = tag_config
+            .tags
+            .iter()
+            .map(|tag| {
+                let examples_str = if !tag.examples.is_empty() {
+                    format!("\n  Examples: {}", tag.examples.join(", "))
+                } else {
+                    String::new()
+                };
+                format!("- {}: {}{}", tag.name, tag.description, examples_str)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        format!(
+            r#"You are a code task classifier. Be CONSERVATIVE - only select tags that CLEARLY match.
+
+{}
+
+Which tags apply? Choose from:
+{}
+
+IMPORTANT:
+- Only select tags if there is CLEAR evidence in the task/intent. If the task is vague or generic (like "help me" or "do something"), return an empty list
+- Do NOT guess or assume. When in doubt, return an empty list
+- "confidence" is how certain you are that the tag applies, from 0 to 100
+
+Reply with JSON only: {{"tags": [{{"name": "<tag>", "confidence": <0-100>}}]}}"#,
+            prompt_context, tag_list
+        )
+    }
+
+    /// JSON schema for the structured tagging reply. Tag names are restricted to the
+    /// loaded tag config so constrained decoding cannot invent tags.
+    fn tag_response_schema(tag_config: &LlmTagConfig) -> serde_json::Value {
+        let tag_names: Vec<&str> = tag_config.tags.iter().map(|t| t.name.as_str()).collect();
+
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "tags": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "name": {"type": "string", "enum": tag_names},
+                            "confidence": {"type": "integer", "minimum": 0, "maximum": 100}
+                        },
+                        "required": ["name", "confidence"],
+                        "additionalProperties": false
+                    }
+                }
+            },
+            "required": ["tags"],
+            "additionalProperties": false
+        })
+    }
+
+    /// Validate a structured tagging reply against `tag_response_schema`.
+    /// Duplicate tags keep their highest confidence.
+    fn parse_structured_tags(
+        response: &str,
+        tag_config: &LlmTagConfig,
+    ) -> std::result::Result<Vec<TagEntry>, String> {
+        let parsed: TagResponse =
+            serde_json::from_str(response.trim()).map_err(|e| format!("invalid JSON: {}", e))?;
+
+        let mut entries: Vec<TagEntry> = Vec::new();
+        for entry in parsed.tags {
+            if !tag_config.tags.iter().any(|t| t.name == entry.name) {
+                return Err(format!("unknown tag '{}'", entry.name));
+            }
+            if !(0..=100).contains(&entry.confidence) {
+                return Err(format!(
+                    "confidence {} for tag '{}' is outside 0-100",
+                    entry.confidence, entry.name
+                ));
+            }
+
+            match entries.iter_mut().find(|e| e.name == entry.name) {
+                Some(existing) => existing.confidence = existing.confidence.max(entry.confidence),
+                None => entries.push(entry),
+            }
+        }
+
+        Ok(entries)
+    }
+
     fn parse_tag_list(&self, response: &str, tag_config: &LlmTagConfig) -> Vec<String> {
         let tag_names: Vec<String> = tag_config.tags.iter().map(|tag| tag.name.clone()).collect();
         let mut found_tags = Vec::new();
@@ -531,6 +638,7 @@ mod tests {
             model_source: ModelSource::Ollama,
             thinking_mode: true,
             temperature: None, // Use defaults
+            ..Config::default()
         }
     }
 
@@ -541,16 +649,19 @@ mod tests {
                     name: "authentication".to_string(),
                     description: "User authentication and authorization".to_string(),
                     examples: vec!["login".to_string(), "password".to_string()],
+                    min_confidence: None,
                 },
                 LlmTagDefinition {
                     name: "database".to_string(),
                     description: "Database operations".to_string(),
                     examples: vec!["SQL".to_string(), "migrations".to_string()],
+                    min_confidence: None,
                 },
                 LlmTagDefinition {
                     name: "api".to_string(),
                     description: "API endpoints".to_string(),
                     examples: vec!["REST".to_string(), "GraphQL".to_string()],
+                    min_confidence: None,
                 },
             ],
         }
@@ -744,49 +855,12 @@ mod tests {
 
         // This test just verifies the function returns a Result without panicking
         // The actual result depends on whether Ollama is installed on the test system
-        let result = manager.check_ollama_installed();
+        let result = manager.check_backend_installed();
         assert!(result.is_ok());
         // Result is a boolean - either true or false is valid
         let _installed = result.unwrap();
     }
 
-    #[tokio::test]
-    async fn test_check_model_name_valid_empty_string() {
-        let config = create_test_config();
-        let manager = ModelManager::new(config).unwrap();
-
-        let result = manager.check_model_name_valid("").await;
-        assert!(result.is_ok());
-        assert!(!result.unwrap());
-    }
-
-    #[test]
-    fn test_check_model_name_valid_logic() {
-        // Test the validation logic without network calls
-        // Empty model names should be rejected by check_model_name_valid
-        let empty: String = String::new();
-        assert!(empty.is_empty());
-
-        let llama = String::from("llama3");
-        assert!(!llama.is_empty());
-
-        // Model names with special characters that could be dangerous
-        let valid_names = vec!["llama3", "qwen2.5:7b", "mistral:latest", "codellama:13b"];
-        for name in valid_names {
-            let name_str = name.to_string();
-            assert!(
-                !name_str.is_empty(),
-                "Valid model name should not be empty: {}",
-                name
-            );
-            assert!(
-                !name_str.contains(".."),
-                "Model name should not contain ..: {}",
-                name
-            );
-        }
-    }
-
     #[test]
     fn test_model_name_parsing() {
         // Test model name parsing logic used in check_model_exists
@@ -799,20 +873,6 @@ mod tests {
         assert_eq!(model_base_no_tag, "llama3");
     }
 
-    #[test]
-    fn test_url_construction() {
-        // Test URL construction logic used in HTTP calls
-        let config = create_test_config();
-        let generate_url = format!("{}/api/generate", config.ollama_url);
-        assert_eq!(generate_url, "http://localhost:11434/api/generate");
-
-        let tags_url = format!("{}/api/tags", config.ollama_url);
-        assert_eq!(tags_url, "http://localhost:11434/api/tags");
-
-        let ps_url = format!("{}/api/ps", config.ollama_url);
-        assert_eq!(ps_url, "http://localhost:11434/api/ps");
-    }
-
     #[tokio::test]
     async fn test_check_ollama_running_success() {
         use wiremock::matchers::{method, path};
@@ -830,7 +890,7 @@ mod tests {
         config.ollama_url = mock_server.uri();
         let manager = ModelManager::new(config).unwrap();
 
-        let result = manager.check_ollama_running().await;
+        let result = manager.check_backend_running().await;
         assert!(result.is_ok());
         assert!(result.unwrap());
     }
@@ -841,79 +901,347 @@ mod tests {
         config.ollama_url = "http://localhost:99999".to_string();
         let manager = ModelManager::new(config).unwrap();
 
-        let result = manager.check_ollama_running().await;
+        let result = manager.check_backend_running().await;
         assert!(result.is_ok());
         assert!(!result.unwrap());
     }
 
-    #[test]
-    fn test_ollama_request_serialization() {
-        let request = OllamaRequest {
-            model: "qwen2.5-coder:7b".to_string(),
-            prompt: "test prompt".to_string(),
-            stream: false,
-            options: OllamaOptions {
-                temperature: 0.1,
-                num_predict: 100,
-            },
-            think: None,
-        };
+    /// Backend stand-in that returns a canned completion and model list
+    #[derive(Default)]
+    struct MockBackend {
+        response: String,
+        /// Reply to requests carrying a `format` schema (defaults to `response`)
+        structured_response: Option<String>,
+        /// Reject `format` schemas like a server without structured output support
+        reject_format: bool,
+        models: Vec<String>,
+        /// Number of generate calls made with a `format` schema
+        structured_calls: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmBackend for MockBackend {
+        fn name(&self) -> &'static str {
+            "Mock"
+        }
+
+        fn not_installed_message(&self) -> String {
+            "Mock is not installed".to_string()
+        }
+
+        fn check_installed(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn is_running(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn start(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn generate(&self, request: &GenerateRequest) -> Result<backend::GenerateResponse> {
+            let mut text = self.response.clone();
+            if request.format.is_some() {
+                self.structured_calls.fetch_add(1, Ordering::Relaxed);
+                if self.reject_format {
+                    return Err(BackendError::StructuredOutputUnsupported {
+                        backend: "Mock",
+                        status: reqwest::StatusCode::BAD_REQUEST,
+                    }
+                    .into());
+                }
+                if let Some(ref structured) = self.structured_response {
+                    text = structured.clone();
+                }
+            }
+
+            Ok(backend::GenerateResponse {
+                text,
+                thinking: None,
+            })
+        }
+
+        async fn list_models(&self) -> Result<Vec<String>> {
+            Ok(self.models.clone())
+        }
 
-        let json = serde_json::to_string(&request).unwrap();
-        assert!(json.contains("qwen2.5-coder:7b"));
-        assert!(json.contains("test prompt"));
-        assert!(json.contains("\"stream\":false"));
-        assert!(json.contains("0.1"));
-        assert!(json.contains("100"));
-        // think: None should be skipped in serialization
-        assert!(!json.contains("think"));
+        async fn is_loaded(&self, model: &str) -> Result<bool> {
+            Ok(self.models.iter().any(|m| m == model))
+        }
+
+        async fn pull(&self, _model: &str, on_progress: &mut (dyn FnMut(u8) + Send)) -> Result<()> {
+            on_progress(100);
+            Ok(())
+        }
+
+        async fn load(&self, _model: &str) -> Result<()> {
+            Ok(())
+        }
     }
 
-    #[test]
-    fn test_ollama_request_with_thinking() {
-        let request = OllamaRequest {
-            model: "deepseek-r1:7b".to_string(),
-            prompt: "test prompt".to_string(),
-            stream: false,
-            options: OllamaOptions {
-                temperature: 0.1,
-                num_predict: 500,
-            },
-            think: Some(true),
+    #[tokio::test]
+    async fn test_check_model_name_valid_without_model_library() {
+        let manager =
+            ModelManager::with_backend(create_test_config(), Box::new(MockBackend::default()));
+        assert_eq!(
+            manager.check_model_name_valid("llama3").await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_identify_tags_with_custom_backend() {
+        let manager = ModelManager::with_backend(
+            create_test_config(),
+            Box::new(MockBackend {
+                response: "1, 3".to_string(),
+                ..MockBackend::default()
+            }),
+        );
+        let input = ClassificationInput {
+            task: "Fix login endpoint".to_string(),
+            intent: "help debug an issue".to_string(),
+            original_prompt: None,
+            associated_files: None,
+            git_context: None,
+            agent_config_path: None,
+            rules_config_path: None,
+            llm_tags_path: None,
         };
 
-        let json = serde_json::to_string(&request).unwrap();
-        assert!(json.contains("\"think\":true"));
+        let tags = manager
+            .identify_tags(&input, &create_test_tag_config())
+            .await
+            .unwrap();
+
+        assert_eq!(tag_names(&tags), vec!["authentication", "api"]);
+        // The numbered-list prompt carries no confidence
+        assert!(tags.iter().all(|t| t.confidence == LEGACY_TAG_CONFIDENCE));
+        assert_eq!(manager.backend_name(), "Mock");
     }
 
-    #[test]
-    fn test_parse_percentage_basic() {
-        assert_eq!(ModelManager::parse_percentage("45%"), Some(45));
-        assert_eq!(ModelManager::parse_percentage("100%"), Some(100));
-        assert_eq!(ModelManager::parse_percentage("0%"), Some(0));
+    #[tokio::test]
+    async fn test_ensure_ready_requires_loaded_model() {
+        let manager =
+            ModelManager::with_backend(create_test_config(), Box::new(MockBackend::default()));
+        let err = manager.ensure_ready().await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BackendError>(),
+            Some(BackendError::NotReady(message)) if message.starts_with("Model not loaded")
+        ));
+
+        let config = create_test_config();
+        let manager = ModelManager::with_backend(
+            config.clone(),
+            Box::new(MockBackend {
+                models: vec![config.effective_model_name()],
+                ..MockBackend::default()
+            }),
+        );
+        assert!(manager.ensure_ready().await.is_ok());
     }
 
-    #[test]
-    fn test_parse_percentage_with_text() {
-        assert_eq!(
-            ModelManager::parse_percentage("pulling abc123... 67%"),
-            Some(67)
+    #[tokio::test]
+    async fn test_check_model_exists_with_custom_backend() {
+        let manager = ModelManager::with_backend(
+            create_test_config(),
+            Box::new(MockBackend {
+                models: vec!["qwen2.5-coder:latest".to_string()],
+                ..MockBackend::default()
+            }),
         );
+
+        assert!(manager.check_model_exists().await.unwrap());
+        assert!(!manager.check_model_loaded().await.unwrap());
+    }
+
+    fn tag_names(tags: &[TagResult]) -> Vec<&str> {
+        tags.iter().map(|t| t.tag.as_str()).collect()
+    }
+
+    fn create_test_input() -> ClassificationInput {
+        ClassificationInput {
+            task: "Fix login endpoint".to_string(),
+            intent: "help debug an issue".to_string(),
+            original_prompt: None,
+            associated_files: None,
+            git_context: None,
+            agent_config_path: None,
+            rules_config_path: None,
+            llm_tags_path: None,
+        }
+    }
+
+    #[test]
+    fn test_tag_response_schema_enumerates_tags() {
+        let schema = ModelManager::tag_response_schema(&create_test_tag_config());
         assert_eq!(
-            ModelManager::parse_percentage("downloading model 89%"),
-            Some(89)
+            schema["properties"]["tags"]["items"]["properties"]["name"]["enum"],
+            serde_json::json!(["authentication", "database", "api"])
         );
+        assert_eq!(schema["required"], serde_json::json!(["tags"]));
     }
 
     #[test]
-    fn test_parse_percentage_no_percent() {
-        assert_eq!(ModelManager::parse_percentage("pulling manifest"), None);
-        assert_eq!(ModelManager::parse_percentage("verifying"), None);
-        assert_eq!(ModelManager::parse_percentage(""), None);
+    fn test_parse_structured_tags_valid() {
+        let entries = ModelManager::parse_structured_tags(
+            r#"{"tags": [{"name": "api", "confidence": 80}, {"name": "authentication", "confidence": 95}, {"name": "api", "confidence": 90}]}"#,
+            &create_test_tag_config(),
+        )
+        .unwrap();
+
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["api", "authentication"]);
+        assert_eq!(entries[0].confidence, 90);
     }
 
     #[test]
-    fn test_parse_percentage_caps_at_100() {
-        assert_eq!(ModelManager::parse_percentage("150%"), Some(100));
+    fn test_parse_structured_tags_rejects_schema_violations() {
+        let tag_config = create_test_tag_config();
+        // Free text, as a model ignoring the schema would produce
+        assert!(ModelManager::parse_structured_tags("I'd pick 3 but not 12", &tag_config).is_err());
+        // Tag outside the enum
+        assert!(ModelManager::parse_structured_tags(
+            r#"{"tags": [{"name": "frontend", "confidence": 90}]}"#,
+            &tag_config
+        )
+        .is_err());
+        // Confidence out of range
+        assert!(ModelManager::parse_structured_tags(
+            r#"{"tags": [{"name": "api", "confidence": 120}]}"#,
+            &tag_config
+        )
+        .is_err());
+        // Missing and unknown fields
+        assert!(
+            ModelManager::parse_structured_tags(r#"{"tags": [{"name": "api"}]}"#, &tag_config)
+                .is_err()
+        );
+        assert!(ModelManager::parse_structured_tags(
+            r#"{"tags": [], "reason": "none apply"}"#,
+            &tag_config
+        )
+        .is_err());
+        // An empty list is a valid "no tags" answer
+        assert!(
+            ModelManager::parse_structured_tags(r#"{"tags": []}"#, &tag_config)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_identify_tags_uses_structured_output() {
+        let manager = ModelManager::with_backend(
+            create_test_config(),
+            Box::new(MockBackend {
+                // The legacy reply would yield a wrong tag if it were ever used
+                response: "2".to_string(),
+                structured_response: Some(
+                    r#"{"tags": [{"name": "authentication", "confidence": 92}]}"#.to_string(),
+                ),
+                ..MockBackend::default()
+            }),
+        );
+
+        let tags = manager
+            .identify_tags(&create_test_input(), &create_test_tag_config())
+            .await
+            .unwrap();
+        assert_eq!(tag_names(&tags), vec!["authentication"]);
+        assert_eq!(tags[0].confidence, 92);
+    }
+
+    #[tokio::test]
+    async fn test_identify_tags_falls_back_when_format_unsupported() {
+        let structured_calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let manager = ModelManager::with_backend(
+            create_test_config(),
+            Box::new(MockBackend {
+                response: "1".to_string(),
+                reject_format: true,
+                structured_calls: structured_calls.clone(),
+                ..MockBackend::default()
+            }),
+        );
+
+        for _ in 0..2 {
+            let tags = manager
+                .identify_tags(&create_test_input(), &create_test_tag_config())
+                .await
+                .unwrap();
+            assert_eq!(tag_names(&tags), vec!["authentication"]);
+        }
+        // Only the first call tried a schema; later calls go straight to the legacy prompt
+        assert_eq!(structured_calls.load(Ordering::Relaxed), 1);
+        assert!(!manager.structured_output_supported.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_identify_tags_legacy_when_structured_output_disabled() {
+        let mut config = create_test_config();
+        config.structured_output = false;
+        let structured_calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let manager = ModelManager::with_backend(
+            config,
+            Box::new(MockBackend {
+                response: "3".to_string(),
+                structured_calls: structured_calls.clone(),
+                ..MockBackend::default()
+            }),
+        );
+        let tags = manager
+            .identify_tags(&create_test_input(), &create_test_tag_config())
+            .await
+            .unwrap();
+
+        assert_eq!(tag_names(&tags), vec!["api"]);
+        assert_eq!(structured_calls.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_apply_min_confidence() {
+        let mut tag_config = create_test_tag_config();
+        tag_config.tags[0].min_confidence = Some(90);
+
+        let kept = ModelManager::apply_min_confidence(
+            vec![
+                ("authentication".to_string(), 80),
+                ("database".to_string(), 0),
+                ("api".to_string(), 40),
+            ],
+            &tag_config,
+        );
+
+        // authentication is below its threshold, database scored zero
+        assert_eq!(kept, vec![("api".to_string(), 40)]);
+    }
+
+    #[tokio::test]
+    async fn test_identify_tags_returns_confidence_and_files() {
+        let mut tag_config = create_test_tag_config();
+        tag_config.tags[2].min_confidence = Some(70);
+
+        let manager = ModelManager::with_backend(
+            create_test_config(),
+            Box::new(MockBackend {
+                structured_response: Some(
+                    r#"{"tags": [{"name": "authentication", "confidence": 75}, {"name": "api", "confidence": 60}]}"#
+                        .to_string(),
+                ),
+                ..MockBackend::default()
+            }),
+        );
+        let mut input = create_test_input();
+        input.associated_files = Some(vec!["src/auth.rs".to_string()]);
+
+        let tags = manager.identify_tags(&input, &tag_config).await.unwrap();
+
+        assert_eq!(tag_names(&tags), vec!["authentication"]);
+        assert_eq!(tags[0].confidence, 75);
+        assert_eq!(tags[0].files, vec!["src/auth.rs".to_string()]);
     }
 }
@@ -0,0 +1,274 @@
+// Hot reload of the startup configs.
+//
+// The classifier keeps its agent, tag and rules configs in a `ConfigSet` that is swapped
+// as a unit. `ConfigWatcher` polls the files they come from, and `ConfigDiff` describes
+// what a reload changed so the client can be told.
+use crate::discovery::ConfigSources;
+use crate::types::{
+    AgentDefinition, LlmTagConfig, LlmTagDefinition, Rule, RulesConfig, UserConfig,
+};
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// How often the watcher checks the config files (default for `CONFIG_POLL_INTERVAL_MS`)
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The configs the classifier routes with. Requests take a snapshot, so a reload never
+/// mixes old and new configs within one request.
+#[derive(Debug, Clone)]
+pub struct ConfigSet {
+    pub user_config: Arc<UserConfig>,
+    pub tag_config: Arc<LlmTagConfig>,
+    pub rules_config: Arc<RulesConfig>,
+    pub sources: ConfigSources,
+    /// Files merged into the configs through `extends`
+    pub base_files: Vec<PathBuf>,
+}
+
+impl ConfigSet {
+    pub fn new(
+        user_config: UserConfig,
+        tag_config: LlmTagConfig,
+        rules_config: RulesConfig,
+    ) -> Self {
+        Self {
+            user_config: Arc::new(user_config),
+            tag_config: Arc::new(tag_config),
+            rules_config: Arc::new(rules_config),
+            sources: ConfigSources::default(),
+            base_files: vec![],
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self::new(
+            UserConfig { agents: vec![] },
+            LlmTagConfig { tags: vec![] },
+            RulesConfig { rules: vec![] },
+        )
+    }
+}
+
+/// Names of the entries added, removed or changed in one config
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ListDiff {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changed: Vec<String>,
+}
+
+impl ListDiff {
+    /// Compare two lists of named entries. Entries are matched by name and compared by
+    /// their serialized form.
+    fn between<T: Serialize>(old: &[(String, &T)], new: &[(String, &T)]) -> Self {
+        let value = |entry: &T| serde_json::to_value(entry).ok();
+        let mut diff = ListDiff::default();
+
+        for (name, entry) in new {
+            match old.iter().find(|(n, _)| n == name) {
+                None => diff.added.push(name.clone()),
+                Some((_, old_entry)) if value(old_entry) != value(entry) => {
+                    diff.changed.push(name.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        for (name, _) in old {
+            if !new.iter().any(|(n, _)| n == name) {
+                diff.removed.push(name.clone());
+            }
+        }
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for ListDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = [
+            ("+", &self.added),
+            ("-", &self.removed),
+            ("~", &self.changed),
+        ]
+        .iter()
+        .filter(|(_, names)| !names.is_empty())
+        .map(|(sign, names)| format!("{}{}", sign, names.join(&format!(", {}", sign))))
+        .collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// What a reload changed, per config
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConfigDiff {
+    pub agents: ListDiff,
+    pub tags: ListDiff,
+    pub rules: ListDiff,
+}
+
+impl ConfigDiff {
+    pub fn between(old: &ConfigSet, new: &ConfigSet) -> Self {
+        Self {
+            agents: ListDiff::between(&named_agents(old), &named_agents(new)),
+            tags: ListDiff::between(&named_tags(old), &named_tags(new)),
+            rules: ListDiff::between(&named_rules(old), &named_rules(new)),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.agents.is_empty() && self.tags.is_empty() && self.rules.is_empty()
+    }
+}
+
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no changes");
+        }
+        let parts: Vec<String> = [
+            ("agents", &self.agents),
+            ("tags", &self.tags),
+            ("rules", &self.rules),
+        ]
+        .iter()
+        .filter(|(_, diff)| !diff.is_empty())
+        .map(|(name, diff)| format!("{}: {}", name, diff))
+        .collect();
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+fn named_agents(configs: &ConfigSet) -> Vec<(String, &AgentDefinition)> {
+    configs
+        .user_config
+        .agents
+        .iter()
+        .map(|a| (a.name.clone(), a))
+        .collect()
+}
+
+fn named_tags(configs: &ConfigSet) -> Vec<(String, &LlmTagDefinition)> {
+    configs
+        .tag_config
+        .tags
+        .iter()
+        .map(|t| (t.name.clone(), t))
+        .collect()
+}
+
+fn named_rules(configs: &ConfigSet) -> Vec<(String, &Rule)> {
+    configs
+        .rules_config
+        .rules
+        .iter()
+        .enumerate()
+        .map(|(idx, r)| (r.display_name(idx), r))
+        .collect()
+}
+
+/// Detects changes to config files by polling their modification time and size, which
+/// also catches editors that replace a file instead of writing it in place
+pub struct ConfigWatcher {
+    paths: Vec<PathBuf>,
+    stamps: Vec<Option<(SystemTime, u64)>>,
+}
+
+impl ConfigWatcher {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let stamps = paths.iter().map(Self::stamp).collect();
+        Self { paths, stamps }
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    fn stamp(path: &PathBuf) -> Option<(SystemTime, u64)> {
+        let metadata = fs::metadata(path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+
+    /// Whether any file was modified, created or removed since the previous poll
+    pub fn poll(&mut self) -> bool {
+        let stamps: Vec<_> = self.paths.iter().map(Self::stamp).collect();
+        let changed = stamps != self.stamps;
+        self.stamps = stamps;
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_set(agents: &str, rules: &str) -> ConfigSet {
+        ConfigSet::new(
+            serde_json::from_str(agents).unwrap(),
+            serde_json::from_str(r#"{"tags": []}"#).unwrap(),
+            serde_json::from_str(rules).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_config_diff_reports_added_removed_and_changed() {
+        let old = config_set(
+            r#"{"agents": [{"name": "a", "description": "A"}, {"name": "b", "description": "B"}]}"#,
+            r#"{"rules": [
+                {"description": "Rust", "conditions": {"file_pattern": "*.rs"}, "route_to_subagents": ["a"]},
+                {"description": "Docs", "conditions": {"file_pattern": "*.md"}, "route_to_subagents": ["b"]}
+            ]}"#,
+        );
+        let new = config_set(
+            r#"{"agents": [{"name": "a", "description": "A"}, {"name": "c", "description": "C"}]}"#,
+            r#"{"rules": [
+                {"description": "Rust", "conditions": {"file_pattern": "src/**/*.rs"}, "route_to_subagents": ["a"]},
+                {"description": "Docs", "conditions": {"file_pattern": "*.md"}, "route_to_subagents": ["b"]}
+            ]}"#,
+        );
+
+        let diff = ConfigDiff::between(&old, &new);
+        assert_eq!(
+            diff.agents,
+            ListDiff {
+                added: vec!["c".to_string()],
+                removed: vec!["b".to_string()],
+                changed: vec![],
+            }
+        );
+        assert!(diff.tags.is_empty());
+        assert_eq!(diff.rules.changed, vec!["Rust"]);
+        assert_eq!(diff.to_string(), "agents: +c, -b; rules: ~Rust");
+        assert!(ConfigDiff::between(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_config_watcher_detects_changes() {
+        let dir =
+            std::env::temp_dir().join(format!("agent-router-watch-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("rules.json");
+        fs::write(&path, r#"{"rules": []}"#).unwrap();
+
+        let mut watcher = ConfigWatcher::new(vec![path.clone()]);
+        assert!(!watcher.poll());
+
+        // A size change is detected even within the filesystem's mtime granularity
+        fs::write(&path, r#"{"rules": [ ]}"#).unwrap();
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+
+        fs::remove_file(&path).unwrap();
+        assert!(watcher.poll());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
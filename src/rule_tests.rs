@@ -0,0 +1,356 @@
+// Golden-file tests for rules configs, run by the `test` subcommand.
+//
+// A fixture pins the request and the tags the LLM would return, then states which agents
+// must (or must not) be routed to. Cases run through the same rule evaluation as
+// `get_instructions`, with the tags injected, so no backend is needed.
+use crate::classifier::Classifier;
+use crate::model_manager::ModelManager;
+use crate::types::*;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// A fixture file: a list of test cases
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleTestSuite {
+    pub tests: Vec<RuleTestCase>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleTestCase {
+    pub name: String,
+    pub input: RuleTestInput,
+    #[serde(default)]
+    pub expect: RuleTestExpectation,
+}
+
+/// The request to route. `tags` stand in for the LLM's answer.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleTestInput {
+    pub task: String,
+    pub intent: String,
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub files: Vec<String>,
+    #[serde(default)]
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<InjectedTag>,
+}
+
+/// A tag as the LLM would return it: a bare name (confidence 100) or a name and confidence
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum InjectedTag {
+    Name(String),
+    Scored { tag: String, confidence: u8 },
+}
+
+impl InjectedTag {
+    fn scored(&self) -> (String, u8) {
+        match self {
+            InjectedTag::Name(tag) => (tag.clone(), 100),
+            InjectedTag::Scored { tag, confidence } => (tag.clone(), *confidence),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RuleTestExpectation {
+    /// Agents that must be routed to
+    #[serde(default)]
+    pub present: Vec<String>,
+    /// Agents that must not be routed to
+    #[serde(default)]
+    pub absent: Vec<String>,
+    /// Inclusive confidence bounds per agent; the agent must also be routed to
+    #[serde(default)]
+    pub confidence: BTreeMap<String, ConfidenceBounds>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ConfidenceBounds {
+    #[serde(default)]
+    pub min: Option<u8>,
+    #[serde(default)]
+    pub max: Option<u8>,
+}
+
+impl fmt::Display for ConfidenceBounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..={}", self.min.unwrap_or(0), self.max.unwrap_or(100))
+    }
+}
+
+/// One way a case's result differs from its expectation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// An expected agent was not routed to
+    Missing { agent: String },
+    /// An agent listed in `absent` was routed to
+    Unexpected { agent: String },
+    /// An agent's confidence is outside the expected bounds
+    Confidence {
+        agent: String,
+        actual: u8,
+        expected: String,
+    },
+    /// An injected tag is not defined in the tag config, so no rule can use it
+    UnknownTag { tag: String },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Missing { agent } => write!(f, "- {} (expected, not routed)", agent),
+            Mismatch::Unexpected { agent } => write!(f, "+ {} (routed, expected absent)", agent),
+            Mismatch::Confidence {
+                agent,
+                actual,
+                expected,
+            } => write!(
+                f,
+                "~ {} confidence {}, expected {}",
+                agent, actual, expected
+            ),
+            Mismatch::UnknownTag { tag } => {
+                write!(f, "? tag '{}' is not defined in the tag config", tag)
+            }
+        }
+    }
+}
+
+/// The outcome of one case
+#[derive(Debug, Clone)]
+pub struct RuleTestResult {
+    pub name: String,
+    /// Every routed agent with its confidence, in response order
+    pub routed: Vec<(String, u8)>,
+    /// Empty when the case passed
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl RuleTestResult {
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Load a fixture file
+pub fn load_rule_tests(path: &Path) -> Result<RuleTestSuite> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read rule tests from {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse rule tests from {}", path.display()))
+}
+
+/// Run every case in the suite against the given configs
+pub fn run_rule_tests(
+    suite: &RuleTestSuite,
+    user_config: &UserConfig,
+    tag_config: &LlmTagConfig,
+    rules_config: &RulesConfig,
+) -> Result<Vec<RuleTestResult>> {
+    // Never calls the LLM; the backend settings are irrelevant
+    let classifier = Classifier::new(Config {
+        routing_mode: RoutingMode::RulesOnly,
+        ..Config::default()
+    })?;
+
+    Ok(suite
+        .tests
+        .iter()
+        .map(|case| run_case(&classifier, case, user_config, tag_config, rules_config))
+        .collect())
+}
+
+fn run_case(
+    classifier: &Classifier,
+    case: &RuleTestCase,
+    user_config: &UserConfig,
+    tag_config: &LlmTagConfig,
+    rules_config: &RulesConfig,
+) -> RuleTestResult {
+    let input = ClassificationInput {
+        task: case.input.task.clone(),
+        intent: case.input.intent.clone(),
+        original_prompt: case.input.prompt.clone(),
+        associated_files: Some(case.input.files.clone()),
+        git_context: case.input.branch.as_ref().map(|branch| GitContext {
+            branch: branch.clone(),
+            changed_files: vec![],
+            staged_files: vec![],
+            tag: None,
+        }),
+        agent_config_path: None,
+        rules_config_path: None,
+        llm_tags_path: None,
+    };
+    let injected: Vec<(String, u8)> = case.input.tags.iter().map(InjectedTag::scored).collect();
+    // Filter like a real LLM answer, so `min_confidence` applies to injected tags too
+    let tags: Vec<TagResult> = ModelManager::apply_min_confidence(injected.clone(), tag_config)
+        .into_iter()
+        .map(|(tag, confidence)| TagResult {
+            tag,
+            confidence,
+            files: vec![],
+        })
+        .collect();
+
+    let response = classifier.route_with_tags(&input, &tags, rules_config, user_config);
+    let routed: Vec<(String, u8)> = response
+        .instructions
+        .iter()
+        .map(|i| (i.route_to_agent.name.clone(), i.context.confidence))
+        .collect();
+    let confidence_of = |agent: &str| {
+        routed
+            .iter()
+            .find(|(name, _)| name == agent)
+            .map(|(_, confidence)| *confidence)
+    };
+
+    let mut mismatches = Vec::new();
+    for (tag, _) in &injected {
+        if !tag_config.tags.iter().any(|t| &t.name == tag) {
+            mismatches.push(Mismatch::UnknownTag { tag: tag.clone() });
+        }
+    }
+    for agent in &case.expect.present {
+        if confidence_of(agent).is_none() {
+            mismatches.push(Mismatch::Missing {
+                agent: agent.clone(),
+            });
+        }
+    }
+    for agent in &case.expect.absent {
+        if confidence_of(agent).is_some() {
+            mismatches.push(Mismatch::Unexpected {
+                agent: agent.clone(),
+            });
+        }
+    }
+    for (agent, bounds) in &case.expect.confidence {
+        match confidence_of(agent) {
+            None if !case.expect.present.contains(agent) => {
+                mismatches.push(Mismatch::Missing {
+                    agent: agent.clone(),
+                });
+            }
+            None => {}
+            Some(actual) => {
+                let in_bounds = bounds.min.is_none_or(|min| actual >= min)
+                    && bounds.max.is_none_or(|max| actual <= max);
+                if !in_bounds {
+                    mismatches.push(Mismatch::Confidence {
+                        agent: agent.clone(),
+                        actual,
+                        expected: bounds.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    RuleTestResult {
+        name: case.name.clone(),
+        routed,
+        mismatches,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configs() -> (UserConfig, LlmTagConfig, RulesConfig) {
+        let user_config = serde_json::from_str(
+            r#"{"agents": [
+                {"name": "rust-reviewer", "description": "Rust"},
+                {"name": "security-auditor", "description": "Security"}
+            ]}"#,
+        )
+        .unwrap();
+        let tag_config = serde_json::from_str(
+            r#"{"tags": [{"name": "security", "description": "Security", "examples": [],
+                         "min_confidence": 70}]}"#,
+        )
+        .unwrap();
+        let rules_config = serde_json::from_str(
+            r#"{"rules": [
+                {"conditions": {"file_pattern": "*.rs"}, "route_to_subagents": ["rust-reviewer"]},
+                {"conditions": {"llm_tag": "security"}, "route_to_subagents": ["security-auditor"]},
+                {"conditions": {"branch_regex": "^docs/"}, "suppress_subagents": ["security-auditor"]}
+            ]}"#,
+        )
+        .unwrap();
+        (user_config, tag_config, rules_config)
+    }
+
+    #[test]
+    fn test_rule_tests_report_mismatches() {
+        let (user_config, tag_config, rules_config) = configs();
+        let suite: RuleTestSuite = serde_json::from_str(
+            r#"{"tests": [
+                {"name": "passes",
+                 "input": {"task": "t", "intent": "i", "files": ["src/lib.rs"],
+                           "tags": [{"tag": "security", "confidence": 80}]},
+                 "expect": {"present": ["rust-reviewer", "security-auditor"],
+                            "confidence": {"security-auditor": {"min": 70, "max": 90}}}},
+                {"name": "fails",
+                 "input": {"task": "t", "intent": "i", "files": ["src/lib.rs"],
+                           "branch": "docs/readme", "tags": ["security", "secrets"]},
+                 "expect": {"present": ["security-auditor"], "absent": ["rust-reviewer"],
+                            "confidence": {"rust-reviewer": {"max": 50}}}},
+                {"name": "below min_confidence",
+                 "input": {"task": "t", "intent": "i",
+                           "tags": [{"tag": "security", "confidence": 60}]},
+                 "expect": {"absent": ["security-auditor"]}}
+            ]}"#,
+        )
+        .unwrap();
+
+        let results = run_rule_tests(&suite, &user_config, &tag_config, &rules_config).unwrap();
+        assert!(results[0].passed(), "{:?}", results[0].mismatches);
+        assert_eq!(
+            results[0].routed,
+            vec![
+                ("rust-reviewer".to_string(), 100),
+                ("security-auditor".to_string(), 80)
+            ]
+        );
+
+        // The docs branch suppresses the security auditor
+        assert_eq!(
+            results[1].mismatches,
+            vec![
+                Mismatch::UnknownTag {
+                    tag: "secrets".to_string()
+                },
+                Mismatch::Missing {
+                    agent: "security-auditor".to_string()
+                },
+                Mismatch::Unexpected {
+                    agent: "rust-reviewer".to_string()
+                },
+                Mismatch::Confidence {
+                    agent: "rust-reviewer".to_string(),
+                    actual: 100,
+                    expected: "0..=50".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            results[1].mismatches[3].to_string(),
+            "~ rust-reviewer confidence 100, expected 0..=50"
+        );
+
+        // Injected tags are dropped below the tag's min_confidence, like LLM answers
+        assert!(results[2].passed(), "{:?}", results[2].mismatches);
+        assert!(results[2].routed.is_empty());
+    }
+}
@@ -1,7 +1,11 @@
+use crate::discovery::ConfigKind;
+use crate::extends;
+use crate::format::{self, ConfigFormat};
 use crate::types::*;
 use anyhow::{Context, Result};
 use glob::Pattern;
 use regex::Regex;
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -16,16 +20,16 @@ lazy_static::lazy_static! {
     static ref REGEX_CACHE: RegexCache = Arc::new(RwLock::new(HashMap::new()));
 }
 
-// Default config paths
-const DEFAULT_AGENTS_CONFIG: &str = "./config/agents.json";
-const DEFAULT_RULES_CONFIG: &str = "./config/rules.json";
-const DEFAULT_LLM_TAGS_CONFIG: &str = "./config/llm-tags.json";
+// Default configs, compiled in so the server works from any directory
+const BUNDLED_AGENTS_CONFIG: &str = include_str!("../config/agents.json");
+const BUNDLED_RULES_CONFIG: &str = include_str!("../config/rules.json");
+const BUNDLED_LLM_TAGS_CONFIG: &str = include_str!("../config/llm-tags.json");
 
 // Security: Maximum config file size (1MB)
 const MAX_CONFIG_FILE_SIZE: u64 = 1_048_576;
 
 /// Validate and canonicalize a config file path to prevent path traversal attacks
-fn validate_config_path(path: &str) -> Result<PathBuf> {
+pub(crate) fn validate_config_path(path: &str) -> Result<PathBuf> {
     let path = Path::new(path);
 
     // Get the canonical path (resolves symlinks and removes .. components)
@@ -33,9 +37,9 @@ fn validate_config_path(path: &str) -> Result<PathBuf> {
         .canonicalize()
         .with_context(|| format!("Failed to resolve path: {}", path.display()))?;
 
-    // Security: Only allow .json files
-    if canonical.extension().and_then(|s| s.to_str()) != Some("json") {
-        anyhow::bail!("Config files must have .json extension");
+    // Security: Only allow config file extensions
+    if ConfigFormat::from_path(&canonical).is_none() {
+        anyhow::bail!("Config files must have a .json, .yaml, .yml or .toml extension");
     }
 
     // Security: Check file size before reading
@@ -53,6 +57,17 @@ fn validate_config_path(path: &str) -> Result<PathBuf> {
     Ok(canonical)
 }
 
+/// Deserialize a config file, merged with the files it `extends`
+fn parse_config<T: DeserializeOwned>(path: &Path, content: &str, kind: ConfigKind) -> Result<T> {
+    let format = ConfigFormat::from_path(path).unwrap_or(ConfigFormat::Json);
+    let value: serde_json::Value = format.parse(content)?;
+    if !extends::is_layered(&value) {
+        // From the text, so errors keep their line and column
+        return format.parse(content);
+    }
+    format::from_merged(extends::resolve(path, value, kind)?)
+}
+
 /// Load user agent configuration from file or use default path
 pub fn load_user_config(path: &str) -> Result<UserConfig> {
     let validated_path = validate_config_path(path)?;
@@ -63,12 +78,13 @@ pub fn load_user_config(path: &str) -> Result<UserConfig> {
             validated_path.display()
         )
     })?;
-    let config: UserConfig = serde_json::from_str(&content).with_context(|| {
-        format!(
-            "Failed to parse agent config from {}",
-            validated_path.display()
-        )
-    })?;
+    let config: UserConfig = parse_config(&validated_path, &content, ConfigKind::Agents)
+        .with_context(|| {
+            format!(
+                "Failed to parse agent config from {}",
+                validated_path.display()
+            )
+        })?;
 
     // Validate config content
     config.validate().map_err(|e| {
@@ -82,9 +98,14 @@ pub fn load_user_config(path: &str) -> Result<UserConfig> {
     Ok(config)
 }
 
-/// Load default user agent configuration
+/// Load the bundled default agent configuration
 pub fn default_user_config() -> Result<UserConfig> {
-    load_user_config(DEFAULT_AGENTS_CONFIG)
+    let config: UserConfig = serde_json::from_str(BUNDLED_AGENTS_CONFIG)
+        .context("Failed to parse bundled agent config")?;
+    config
+        .validate()
+        .map_err(|e| anyhow::anyhow!("Invalid bundled agent config: {}", e))?;
+    Ok(config)
 }
 
 /// Load LLM tag configuration from file or use default path
@@ -97,12 +118,13 @@ pub fn load_llm_tag_config(path: &str) -> Result<LlmTagConfig> {
             validated_path.display()
         )
     })?;
-    let config: LlmTagConfig = serde_json::from_str(&content).with_context(|| {
-        format!(
-            "Failed to parse LLM tag config from {}",
-            validated_path.display()
-        )
-    })?;
+    let config: LlmTagConfig = parse_config(&validated_path, &content, ConfigKind::LlmTags)
+        .with_context(|| {
+            format!(
+                "Failed to parse LLM tag config from {}",
+                validated_path.display()
+            )
+        })?;
 
     // Validate config content
     config.validate().map_err(|e| {
@@ -116,9 +138,14 @@ pub fn load_llm_tag_config(path: &str) -> Result<LlmTagConfig> {
     Ok(config)
 }
 
-/// Load default LLM tag configuration
+/// Load the bundled default LLM tag configuration
 pub fn default_llm_tag_config() -> Result<LlmTagConfig> {
-    load_llm_tag_config(DEFAULT_LLM_TAGS_CONFIG)
+    let config: LlmTagConfig = serde_json::from_str(BUNDLED_LLM_TAGS_CONFIG)
+        .context("Failed to parse bundled LLM tag config")?;
+    config
+        .validate()
+        .map_err(|e| anyhow::anyhow!("Invalid bundled LLM tag config: {}", e))?;
+    Ok(config)
 }
 
 /// Load rules configuration from file or use default path
@@ -131,12 +158,13 @@ pub fn load_rules_config(path: &str) -> Result<RulesConfig> {
             validated_path.display()
         )
     })?;
-    let config: RulesConfig = serde_json::from_str(&content).with_context(|| {
-        format!(
-            "Failed to parse rules config from {}",
-            validated_path.display()
-        )
-    })?;
+    let config: RulesConfig = parse_config(&validated_path, &content, ConfigKind::Rules)
+        .with_context(|| {
+            format!(
+                "Failed to parse rules config from {}",
+                validated_path.display()
+            )
+        })?;
 
     // Validate config content
     config.validate().map_err(|e| {
@@ -150,13 +178,18 @@ pub fn load_rules_config(path: &str) -> Result<RulesConfig> {
     Ok(config)
 }
 
-/// Load default rules configuration
+/// Load the bundled default rules configuration
 pub fn default_rules_config() -> Result<RulesConfig> {
-    load_rules_config(DEFAULT_RULES_CONFIG)
+    let config: RulesConfig = serde_json::from_str(BUNDLED_RULES_CONFIG)
+        .context("Failed to parse bundled rules config")?;
+    config
+        .validate()
+        .map_err(|e| anyhow::anyhow!("Invalid bundled rules config: {}", e))?;
+    Ok(config)
 }
 
 /// Get or compile a regex pattern from cache
-fn get_compiled_regex(pattern: &str) -> Option<Regex> {
+pub(crate) fn get_compiled_regex(pattern: &str) -> Option<Regex> {
     // Try to get from cache (read lock)
     {
         if let Ok(cache_read) = REGEX_CACHE.read() {
@@ -198,7 +231,7 @@ fn get_compiled_regex(pattern: &str) -> Option<Regex> {
 pub fn apply_rules(input: &ClassificationInput, rules_config: &RulesConfig) -> Vec<String> {
     let mut agents = Vec::new();
 
-    for rule in &rules_config.rules {
+    for rule in rules_config.rules.iter().filter(|r| r.is_active()) {
         if evaluate_conditions(&rule.conditions, input, &[]) {
             for agent in &rule.route_to_subagents {
                 if !agents.contains(agent) {
@@ -208,14 +241,41 @@ pub fn apply_rules(input: &ClassificationInput, rules_config: &RulesConfig) -> V
         }
     }
 
+    let suppressed = suppressed_agents(input, &[], rules_config);
+    agents.retain(|agent| !suppressed.contains(agent));
+
     agents
 }
 
+/// Agents removed by matching `suppress_subagents` rules.
+/// Suppression wins over every rule that routes to the same agent.
+pub fn suppressed_agents(
+    input: &ClassificationInput,
+    llm_tags: &[String],
+    rules_config: &RulesConfig,
+) -> Vec<String> {
+    let mut suppressed = Vec::new();
+
+    for rule in rules_config.rules.iter().filter(|r| r.is_active()) {
+        if !rule.suppress_subagents.is_empty()
+            && evaluate_conditions(&rule.conditions, input, llm_tags)
+        {
+            for agent in &rule.suppress_subagents {
+                if !suppressed.contains(agent) {
+                    suppressed.push(agent.clone());
+                }
+            }
+        }
+    }
+
+    suppressed
+}
+
 /// Apply rules that use LLM tags
 pub fn apply_llm_tag_rules(llm_tags: &[String], rules_config: &RulesConfig) -> Vec<String> {
     let mut agents = Vec::new();
 
-    for rule in &rules_config.rules {
+    for rule in rules_config.rules.iter().filter(|r| r.is_active()) {
         // Only evaluate rules that contain LLM tag conditions
         if rule_contains_llm_tags(&rule.conditions) {
             // Create a minimal input for evaluation (only tags matter)
@@ -249,23 +309,223 @@ fn rule_contains_llm_tags(conditions: &RuleConditions) -> bool {
         RuleConditions::Single(condition) => matches!(condition, Condition::LlmTag(_)),
         RuleConditions::AnyOf { any_of } => any_of.iter().any(rule_contains_llm_tags),
         RuleConditions::AllOf { all_of } => all_of.iter().any(rule_contains_llm_tags),
+        RuleConditions::Not { not } => rule_contains_llm_tags(not),
+        RuleConditions::NoneOf { none_of } => none_of.iter().any(rule_contains_llm_tags),
+    }
+}
+
+/// Check if conditions look at the associated files
+fn contains_file_conditions(conditions: &RuleConditions) -> bool {
+    match conditions {
+        RuleConditions::Single(condition) => {
+            matches!(
+                condition,
+                Condition::FilePattern(_) | Condition::FileRegex(_)
+            )
+        }
+        RuleConditions::AnyOf { any_of: children }
+        | RuleConditions::AllOf { all_of: children }
+        | RuleConditions::NoneOf { none_of: children } => {
+            children.iter().any(contains_file_conditions)
+        }
+        RuleConditions::Not { not } => contains_file_conditions(not),
     }
 }
 
-/// Evaluate rule conditions recursively
+/// The file sets an `all_of` is evaluated against; it holds when it holds for any of them.
+/// A file condition is true when any file matches, except in an `all_of` that pairs a
+/// file condition with a negated one (`not`/`none_of` over file conditions). That `all_of`
+/// is checked one file at a time, so `all_of: [*.ts, not *.test.ts]` matches `a.ts` even
+/// when `a.test.ts` is also in the request.
+pub fn file_scopes<'a>(all_of: &[RuleConditions], files: &'a [String]) -> Vec<&'a [String]> {
+    let excludes_files = |c: &RuleConditions| {
+        matches!(
+            c,
+            RuleConditions::Not { .. } | RuleConditions::NoneOf { .. }
+        ) && contains_file_conditions(c)
+    };
+    let per_file = files.len() > 1
+        && all_of.iter().any(excludes_files)
+        && all_of
+            .iter()
+            .any(|c| !excludes_files(c) && contains_file_conditions(c));
+    if per_file {
+        files.chunks(1).collect()
+    } else {
+        vec![files]
+    }
+}
+
+/// Three-valued `any`: true if any value is, unknown if any is and none is true
+fn any_of_three_valued(values: impl IntoIterator<Item = Option<bool>>) -> Option<bool> {
+    let mut pending = false;
+    for value in values {
+        match value {
+            Some(true) => return Some(true),
+            Some(false) => {}
+            None => pending = true,
+        }
+    }
+    if pending {
+        None
+    } else {
+        Some(false)
+    }
+}
+
+/// Three-valued evaluation before LLM tags are known.
+/// Returns `Some(true)`/`Some(false)` when deterministic conditions already decide the
+/// outcome, or `None` when it still depends on `llm_tag` conditions.
+pub fn pre_evaluate_conditions(
+    conditions: &RuleConditions,
+    input: &ClassificationInput,
+) -> Option<bool> {
+    pre_evaluate_in_scope(conditions, input, &get_files_for_evaluation(input))
+}
+
+fn pre_evaluate_in_scope(
+    conditions: &RuleConditions,
+    input: &ClassificationInput,
+    files: &[String],
+) -> Option<bool> {
+    match conditions {
+        RuleConditions::Single(Condition::LlmTag(_)) => None,
+        RuleConditions::Single(condition) => Some(evaluate_condition(condition, input, files, &[])),
+        RuleConditions::AnyOf { any_of } => any_of_three_valued(
+            any_of
+                .iter()
+                .map(|c| pre_evaluate_in_scope(c, input, files)),
+        ),
+        RuleConditions::AllOf { all_of } => any_of_three_valued(
+            file_scopes(all_of, files)
+                .into_iter()
+                .map(|scope| pre_evaluate_all_of(all_of, input, scope)),
+        ),
+        RuleConditions::Not { not } => pre_evaluate_in_scope(not, input, files).map(|m| !m),
+        RuleConditions::NoneOf { none_of } => any_of_three_valued(
+            none_of
+                .iter()
+                .map(|c| pre_evaluate_in_scope(c, input, files)),
+        )
+        .map(|m| !m),
+    }
+}
+
+fn pre_evaluate_all_of(
+    all_of: &[RuleConditions],
+    input: &ClassificationInput,
+    files: &[String],
+) -> Option<bool> {
+    // An empty all_of never matches
+    if all_of.is_empty() {
+        return Some(false);
+    }
+    let mut pending = false;
+    for c in all_of {
+        match pre_evaluate_in_scope(c, input, files) {
+            Some(false) => return Some(false),
+            Some(true) => {}
+            None => pending = true,
+        }
+    }
+    if pending {
+        None
+    } else {
+        Some(true)
+    }
+}
+
+/// Collect the LLM tags whose presence could still change some rule's outcome.
+/// Tags in rules (or branches) already decided by deterministic conditions are skipped.
+/// Shadow rules count, so their matches can be reported: a shadow rule's `llm_tag`
+/// alone can cause an LLM call. Disabled rules do not count.
+/// An empty result means the LLM does not need to be called.
+pub fn pending_llm_tags(input: &ClassificationInput, rules_config: &RulesConfig) -> Vec<String> {
+    let files = get_files_for_evaluation(input);
+    let mut tags = Vec::new();
+    for rule in rules_config.rules.iter().filter(|r| r.enabled) {
+        if pre_evaluate_conditions(&rule.conditions, input).is_some() {
+            continue;
+        }
+        collect_pending_tags(&rule.conditions, input, &files, &mut tags);
+    }
+    tags
+}
+
+fn collect_pending_tags(
+    conditions: &RuleConditions,
+    input: &ClassificationInput,
+    files: &[String],
+    tags: &mut Vec<String>,
+) {
+    if pre_evaluate_in_scope(conditions, input, files).is_some() {
+        return;
+    }
+    match conditions {
+        RuleConditions::Single(Condition::LlmTag(tag)) => {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        RuleConditions::Single(_) => {}
+        RuleConditions::AllOf { all_of } => {
+            for scope in file_scopes(all_of, files) {
+                if pre_evaluate_all_of(all_of, input, scope).is_none() {
+                    for c in all_of {
+                        collect_pending_tags(c, input, scope, tags);
+                    }
+                }
+            }
+        }
+        RuleConditions::AnyOf { any_of: children }
+        | RuleConditions::NoneOf { none_of: children } => {
+            for c in children {
+                collect_pending_tags(c, input, files, tags);
+            }
+        }
+        RuleConditions::Not { not } => collect_pending_tags(not, input, files, tags),
+    }
+}
+
+/// Evaluate rule conditions against the request's files
 fn evaluate_conditions(
     conditions: &RuleConditions,
     input: &ClassificationInput,
     llm_tags: &[String],
+) -> bool {
+    evaluate_in_scope(
+        conditions,
+        input,
+        &get_files_for_evaluation(input),
+        llm_tags,
+    )
+}
+
+/// Evaluate rule conditions recursively against the files in scope
+fn evaluate_in_scope(
+    conditions: &RuleConditions,
+    input: &ClassificationInput,
+    files: &[String],
+    llm_tags: &[String],
 ) -> bool {
     match conditions {
-        RuleConditions::Single(condition) => evaluate_condition(condition, input, llm_tags),
+        RuleConditions::Single(condition) => evaluate_condition(condition, input, files, llm_tags),
         RuleConditions::AnyOf { any_of } => any_of
             .iter()
-            .any(|c| evaluate_conditions(c, input, llm_tags)),
-        RuleConditions::AllOf { all_of } => all_of
+            .any(|c| evaluate_in_scope(c, input, files, llm_tags)),
+        // An empty all_of never matches
+        RuleConditions::AllOf { all_of } => {
+            !all_of.is_empty()
+                && file_scopes(all_of, files).into_iter().any(|scope| {
+                    all_of
+                        .iter()
+                        .all(|c| evaluate_in_scope(c, input, scope, llm_tags))
+                })
+        }
+        RuleConditions::Not { not } => !evaluate_in_scope(not, input, files, llm_tags),
+        RuleConditions::NoneOf { none_of } => !none_of
             .iter()
-            .all(|c| evaluate_conditions(c, input, llm_tags)),
+            .any(|c| evaluate_in_scope(c, input, files, llm_tags)),
     }
 }
 
@@ -279,9 +539,8 @@ pub fn get_files_for_evaluation(input: &ClassificationInput) -> Vec<String> {
     vec![]
 }
 
-/// Evaluate file pattern condition - public for use by classifier
-pub fn evaluate_file_pattern(pattern: &str, input: &ClassificationInput) -> bool {
-    let files = get_files_for_evaluation(input);
+/// Whether any of the files matches the glob - public for use by classifier
+pub fn file_pattern_matches(pattern: &str, files: &[String]) -> bool {
     if files.is_empty() {
         return false;
     }
@@ -292,28 +551,15 @@ pub fn evaluate_file_pattern(pattern: &str, input: &ClassificationInput) -> bool
             return false;
         }
     };
-    for file in &files {
-        if glob_pattern.matches(file) {
-            return true;
-        }
-    }
-    false
+    files.iter().any(|file| glob_pattern.matches(file))
 }
 
-/// Evaluate file regex condition - public for use by classifier
-pub fn evaluate_file_regex(pattern: &str, input: &ClassificationInput) -> bool {
-    let files = get_files_for_evaluation(input);
-    if files.is_empty() {
-        return false;
-    }
-    if let Some(re) = get_compiled_regex(pattern) {
-        for file in &files {
-            if re.is_match(file) {
-                return true;
-            }
-        }
+/// Whether any of the files matches the regex - public for use by classifier
+pub fn file_regex_matches(pattern: &str, files: &[String]) -> bool {
+    match get_compiled_regex(pattern) {
+        Some(re) => files.iter().any(|file| re.is_match(file)),
+        None => false,
     }
-    false
 }
 
 /// Evaluate prompt regex condition - public for use by classifier
@@ -341,49 +587,16 @@ pub fn evaluate_branch_regex(pattern: &str, input: &ClassificationInput) -> bool
     false
 }
 
-/// Evaluate a single condition
+/// Evaluate a single condition; file conditions look only at `files`
 fn evaluate_condition(
     condition: &Condition,
     input: &ClassificationInput,
+    files: &[String],
     llm_tags: &[String],
 ) -> bool {
     match condition {
-        Condition::FilePattern(pattern) => {
-            let files = get_files_for_evaluation(input);
-            if files.is_empty() {
-                return false;
-            }
-            // If pattern is invalid, return false (no match)
-            let glob_pattern = match Pattern::new(pattern) {
-                Ok(p) => p,
-                Err(e) => {
-                    warn!("Invalid glob pattern '{}': {}", pattern, e);
-                    return false;
-                }
-            };
-
-            for file in &files {
-                if glob_pattern.matches(file) {
-                    return true;
-                }
-            }
-            false
-        }
-        Condition::FileRegex(regex_pattern) => {
-            let files = get_files_for_evaluation(input);
-            if files.is_empty() {
-                return false;
-            }
-            // Use cached compiled regex
-            if let Some(re) = get_compiled_regex(regex_pattern) {
-                for file in &files {
-                    if re.is_match(file) {
-                        return true;
-                    }
-                }
-            }
-            false
-        }
+        Condition::FilePattern(pattern) => file_pattern_matches(pattern, files),
+        Condition::FileRegex(regex_pattern) => file_regex_matches(regex_pattern, files),
         Condition::PromptRegex(regex_pattern) => {
             // Check task, intent, and original_prompt
             if let Some(re) = get_compiled_regex(regex_pattern) {
@@ -446,6 +659,7 @@ mod tests {
         RulesConfig {
             rules: vec![
                 Rule {
+                    id: None,
                     description: Some("TypeScript files".to_string()),
                     conditions: RuleConditions::AnyOf {
                         any_of: vec![
@@ -454,20 +668,31 @@ mod tests {
                         ],
                     },
                     route_to_subagents: vec!["language-reviewer-typescript".to_string()],
+                    suppress_subagents: vec![],
+                    enabled: true,
+                    mode: RuleMode::Active,
                 },
                 Rule {
+                    id: None,
                     description: Some("Security files".to_string()),
                     conditions: RuleConditions::Single(Condition::FilePattern(
                         "*auth*".to_string(),
                     )),
                     route_to_subagents: vec!["security-auditor".to_string()],
+                    suppress_subagents: vec![],
+                    enabled: true,
+                    mode: RuleMode::Active,
                 },
                 Rule {
+                    id: None,
                     description: Some("Security tag".to_string()),
                     conditions: RuleConditions::Single(Condition::LlmTag(
                         "security-concern".to_string(),
                     )),
                     route_to_subagents: vec!["security-auditor".to_string()],
+                    suppress_subagents: vec![],
+                    enabled: true,
+                    mode: RuleMode::Active,
                 },
             ],
         }
@@ -531,11 +756,15 @@ mod tests {
     fn test_file_regex_pattern() {
         let rules = RulesConfig {
             rules: vec![Rule {
+                id: None,
                 description: Some("Test files".to_string()),
                 conditions: RuleConditions::Single(Condition::FileRegex(
                     r".*\.test\.ts$".to_string(),
                 )),
                 route_to_subagents: vec!["test-engineer".to_string()],
+                suppress_subagents: vec![],
+                enabled: true,
+                mode: RuleMode::Active,
             }],
         };
 
@@ -554,11 +783,15 @@ mod tests {
     fn test_prompt_regex() {
         let rules = RulesConfig {
             rules: vec![Rule {
+                id: None,
                 description: Some("Security prompts".to_string()),
                 conditions: RuleConditions::Single(Condition::PromptRegex(
                     r"(?i)(security|auth|encrypt)".to_string(),
                 )),
                 route_to_subagents: vec!["security-auditor".to_string()],
+                suppress_subagents: vec![],
+                enabled: true,
+                mode: RuleMode::Active,
             }],
         };
 
@@ -573,11 +806,15 @@ mod tests {
     fn test_branch_regex() {
         let rules = RulesConfig {
             rules: vec![Rule {
+                id: None,
                 description: Some("Feature branches".to_string()),
                 conditions: RuleConditions::Single(Condition::BranchRegex(
                     r"^feature/.*".to_string(),
                 )),
                 route_to_subagents: vec!["code-reviewer".to_string()],
+                suppress_subagents: vec![],
+                enabled: true,
+                mode: RuleMode::Active,
             }],
         };
 
@@ -596,6 +833,7 @@ mod tests {
     fn test_nested_any_of() {
         let rules = RulesConfig {
             rules: vec![Rule {
+                id: None,
                 description: Some("Nested conditions".to_string()),
                 conditions: RuleConditions::AnyOf {
                     any_of: vec![
@@ -609,6 +847,9 @@ mod tests {
                     ],
                 },
                 route_to_subagents: vec!["language-reviewer".to_string()],
+                suppress_subagents: vec![],
+                enabled: true,
+                mode: RuleMode::Active,
             }],
         };
 
@@ -627,6 +868,7 @@ mod tests {
     fn test_nested_all_of() {
         let rules = RulesConfig {
             rules: vec![Rule {
+                id: None,
                 description: Some("Nested all conditions".to_string()),
                 conditions: RuleConditions::AllOf {
                     all_of: vec![
@@ -644,6 +886,9 @@ mod tests {
                     ],
                 },
                 route_to_subagents: vec!["security-auditor".to_string()],
+                suppress_subagents: vec![],
+                enabled: true,
+                mode: RuleMode::Active,
             }],
         };
 
@@ -677,14 +922,22 @@ mod tests {
         let rules = RulesConfig {
             rules: vec![
                 Rule {
+                    id: None,
                     description: Some("TypeScript".to_string()),
                     conditions: RuleConditions::Single(Condition::FilePattern("*.ts".to_string())),
                     route_to_subagents: vec!["code-reviewer".to_string()],
+                    suppress_subagents: vec![],
+                    enabled: true,
+                    mode: RuleMode::Active,
                 },
                 Rule {
+                    id: None,
                     description: Some("JavaScript".to_string()),
                     conditions: RuleConditions::Single(Condition::FilePattern("*.js".to_string())),
                     route_to_subagents: vec!["code-reviewer".to_string()],
+                    suppress_subagents: vec![],
+                    enabled: true,
+                    mode: RuleMode::Active,
                 },
             ],
         };
@@ -705,9 +958,13 @@ mod tests {
     fn test_changed_and_staged_files() {
         let rules = RulesConfig {
             rules: vec![Rule {
+                id: None,
                 description: Some("Python files".to_string()),
                 conditions: RuleConditions::Single(Condition::FilePattern("*.py".to_string())),
                 route_to_subagents: vec!["python-reviewer".to_string()],
+                suppress_subagents: vec![],
+                enabled: true,
+                mode: RuleMode::Active,
             }],
         };
 
@@ -737,6 +994,7 @@ mod tests {
     fn test_all_of_one_fails() {
         let rules = RulesConfig {
             rules: vec![Rule {
+                id: None,
                 description: Some("All conditions must match".to_string()),
                 conditions: RuleConditions::AllOf {
                     all_of: vec![
@@ -745,6 +1003,9 @@ mod tests {
                     ],
                 },
                 route_to_subagents: vec!["ts-reviewer".to_string()],
+                suppress_subagents: vec![],
+                enabled: true,
+                mode: RuleMode::Active,
             }],
         };
 
@@ -763,9 +1024,13 @@ mod tests {
     fn test_invalid_regex_does_not_panic() {
         let rules = RulesConfig {
             rules: vec![Rule {
+                id: None,
                 description: Some("Invalid regex".to_string()),
                 conditions: RuleConditions::Single(Condition::FileRegex("[invalid(".to_string())),
                 route_to_subagents: vec!["test-agent".to_string()],
+                suppress_subagents: vec![],
+                enabled: true,
+                mode: RuleMode::Active,
             }],
         };
 
@@ -785,11 +1050,15 @@ mod tests {
     fn test_glob_special_characters() {
         let rules = RulesConfig {
             rules: vec![Rule {
+                id: None,
                 description: Some("Config files".to_string()),
                 conditions: RuleConditions::Single(Condition::FilePattern(
                     "config/*.json".to_string(),
                 )),
                 route_to_subagents: vec!["config-reviewer".to_string()],
+                suppress_subagents: vec![],
+                enabled: true,
+                mode: RuleMode::Active,
             }],
         };
 
@@ -860,6 +1129,42 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_load_yaml_and_toml_configs() {
+        let dir =
+            std::env::temp_dir().join(format!("agent-router-formats-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let yaml_path = dir.join("agents.yaml");
+        std::fs::write(
+            &yaml_path,
+            "agents:\n  - name: rust-reviewer\n    description: Rust\n    instructions: |\n      Check error handling.\n      Check unsafe blocks.\n",
+        )
+        .unwrap();
+        let toml_path = dir.join("rules.toml");
+        std::fs::write(
+            &toml_path,
+            "[[rules]]\nconditions = { file_regex = '\\.rs$' }\nroute_to_subagents = [\"rust-reviewer\"]\n",
+        )
+        .unwrap();
+
+        let user_config = load_user_config(&yaml_path.to_string_lossy()).unwrap();
+        assert_eq!(
+            user_config.agents[0].instructions.as_deref(),
+            Some("Check error handling.\nCheck unsafe blocks.\n")
+        );
+        let rules_config = load_rules_config(&toml_path.to_string_lossy()).unwrap();
+        assert!(matches!(
+            &rules_config.rules[0].conditions,
+            RuleConditions::Single(Condition::FileRegex(regex)) if regex == "\\.rs$"
+        ));
+
+        // Errors point at the failing field
+        std::fs::write(&yaml_path, "agents:\n  - name: rust-reviewer\n").unwrap();
+        let err = load_user_config(&yaml_path.to_string_lossy()).unwrap_err();
+        assert!(format!("{:#}", err).contains("line 2"), "{:#}", err);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_validate_config_path_security() {
         // Test that path traversal is prevented
@@ -875,7 +1180,10 @@ mod tests {
 
         // Create a temporary config file that's too large
         let temp_dir = env::temp_dir();
-        let temp_path = temp_dir.join("test_large_agents_config.json");
+        let temp_path = temp_dir.join(format!(
+            "test_large_agents_config-{}.json",
+            std::process::id()
+        ));
         let mut file = fs::File::create(&temp_path).unwrap();
         // Write 2MB of data (over the 1MB limit)
         let large_data = format!(r#"{{"agents": [{}]}}"#, "x".repeat(2_000_000));
@@ -899,6 +1207,7 @@ mod tests {
         // Test AllOf branch of rule_contains_llm_tags by using LLM tag rules
         let rule_config = RulesConfig {
             rules: vec![Rule {
+                id: None,
                 description: Some("All of with LLM tag".to_string()),
                 conditions: RuleConditions::AllOf {
                     all_of: vec![
@@ -907,6 +1216,9 @@ mod tests {
                     ],
                 },
                 route_to_subagents: vec!["security-auditor".to_string()],
+                suppress_subagents: vec![],
+                enabled: true,
+                mode: RuleMode::Active,
             }],
         };
 
@@ -920,9 +1232,13 @@ mod tests {
         // Test invalid glob pattern fallback
         let rules = RulesConfig {
             rules: vec![Rule {
+                id: None,
                 description: Some("Invalid glob".to_string()),
                 conditions: RuleConditions::Single(Condition::FilePattern("[invalid".to_string())),
                 route_to_subagents: vec!["test-agent".to_string()],
+                suppress_subagents: vec![],
+                enabled: true,
+                mode: RuleMode::Active,
             }],
         };
 
@@ -943,9 +1259,13 @@ mod tests {
         // Test invalid regex returns false
         let rules = RulesConfig {
             rules: vec![Rule {
+                id: None,
                 description: Some("Invalid regex".to_string()),
                 conditions: RuleConditions::Single(Condition::PromptRegex("[invalid(".to_string())),
                 route_to_subagents: vec!["test-agent".to_string()],
+                suppress_subagents: vec![],
+                enabled: true,
+                mode: RuleMode::Active,
             }],
         };
 
@@ -961,11 +1281,15 @@ mod tests {
         // Test branch regex with no git context
         let rules = RulesConfig {
             rules: vec![Rule {
+                id: None,
                 description: Some("Branch regex".to_string()),
                 conditions: RuleConditions::Single(Condition::BranchRegex(
                     "^feature/.*".to_string(),
                 )),
                 route_to_subagents: vec!["test-agent".to_string()],
+                suppress_subagents: vec![],
+                enabled: true,
+                mode: RuleMode::Active,
             }],
         };
 
@@ -980,4 +1304,372 @@ mod tests {
         // Should not match since there's no git context
         assert!(agents.is_empty());
     }
+
+    fn tag_condition(tag: &str) -> RuleConditions {
+        RuleConditions::Single(Condition::LlmTag(tag.to_string()))
+    }
+
+    fn branch_condition(pattern: &str) -> RuleConditions {
+        RuleConditions::Single(Condition::BranchRegex(pattern.to_string()))
+    }
+
+    fn file_condition(pattern: &str) -> RuleConditions {
+        RuleConditions::Single(Condition::FilePattern(pattern.to_string()))
+    }
+
+    #[test]
+    fn test_pre_evaluate_conditions() {
+        let input = create_test_input(
+            "Fix bug",
+            "debug",
+            Some(vec!["src/auth.rs".to_string()]),
+            None,
+        );
+
+        // Deterministic conditions are decided
+        assert_eq!(
+            pre_evaluate_conditions(&file_condition("*.rs"), &input),
+            Some(true)
+        );
+        assert_eq!(
+            pre_evaluate_conditions(&file_condition("*.ts"), &input),
+            Some(false)
+        );
+        // Tag conditions are pending
+        assert_eq!(
+            pre_evaluate_conditions(&tag_condition("security"), &input),
+            None
+        );
+
+        // all_of with a failed branch is false whatever the tag
+        let all_of = RuleConditions::AllOf {
+            all_of: vec![file_condition("*.ts"), tag_condition("security")],
+        };
+        assert_eq!(pre_evaluate_conditions(&all_of, &input), Some(false));
+        let all_of = RuleConditions::AllOf {
+            all_of: vec![file_condition("*.rs"), tag_condition("security")],
+        };
+        assert_eq!(pre_evaluate_conditions(&all_of, &input), None);
+
+        // any_of with a matched branch is true whatever the tag
+        let any_of = RuleConditions::AnyOf {
+            any_of: vec![file_condition("*.rs"), tag_condition("security")],
+        };
+        assert_eq!(pre_evaluate_conditions(&any_of, &input), Some(true));
+        let any_of = RuleConditions::AnyOf {
+            any_of: vec![file_condition("*.ts"), tag_condition("security")],
+        };
+        assert_eq!(pre_evaluate_conditions(&any_of, &input), None);
+    }
+
+    #[test]
+    fn test_pending_llm_tags() {
+        let input = create_test_input(
+            "Fix bug",
+            "debug",
+            Some(vec!["src/auth.rs".to_string()]),
+            None,
+        );
+        let rule = |conditions| Rule {
+            id: None,
+            description: None,
+            conditions,
+            route_to_subagents: vec!["agent".to_string()],
+            suppress_subagents: vec![],
+            enabled: true,
+            mode: RuleMode::Active,
+        };
+        let rules_config = RulesConfig {
+            rules: vec![
+                // Decided false by the file branch
+                rule(RuleConditions::AllOf {
+                    all_of: vec![file_condition("*.ts"), tag_condition("frontend")],
+                }),
+                // Decided true by the file branch
+                rule(RuleConditions::AnyOf {
+                    any_of: vec![file_condition("*.rs"), tag_condition("rust")],
+                }),
+                // Still depends on "security", but not on the decided "docs" branch
+                rule(RuleConditions::AllOf {
+                    all_of: vec![
+                        file_condition("*.rs"),
+                        RuleConditions::AnyOf {
+                            any_of: vec![
+                                tag_condition("security"),
+                                RuleConditions::AllOf {
+                                    all_of: vec![file_condition("*.md"), tag_condition("docs")],
+                                },
+                            ],
+                        },
+                    ],
+                }),
+                rule(tag_condition("security")),
+                rule(tag_condition("performance")),
+            ],
+        };
+
+        assert_eq!(
+            pending_llm_tags(&input, &rules_config),
+            vec!["security".to_string(), "performance".to_string()]
+        );
+
+        // Without any tag rules there is nothing to ask the LLM
+        let rules_config = RulesConfig {
+            rules: vec![rule(file_condition("*.rs"))],
+        };
+        assert!(pending_llm_tags(&input, &rules_config).is_empty());
+
+        // A shadow rule's tags are pending so its match can be reported; a disabled rule's are not
+        let rules_config = RulesConfig {
+            rules: vec![
+                Rule {
+                    mode: RuleMode::Shadow,
+                    ..rule(tag_condition("security"))
+                },
+                Rule {
+                    enabled: false,
+                    ..rule(tag_condition("performance"))
+                },
+            ],
+        };
+        assert_eq!(
+            pending_llm_tags(&input, &rules_config),
+            vec!["security".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_file_conditions_apply_per_file() {
+        let input = create_test_input(
+            "Add tests",
+            "implement feature",
+            Some(vec!["src/a.ts".to_string(), "src/a.test.ts".to_string()]),
+            None,
+        );
+        let not_tests = || RuleConditions::Not {
+            not: Box::new(file_condition("*.test.ts")),
+        };
+        let ts_except_tests = RuleConditions::AllOf {
+            all_of: vec![file_condition("*.ts"), not_tests()],
+        };
+        assert!(evaluate_conditions(&ts_except_tests, &input, &[]));
+        assert_eq!(
+            pre_evaluate_conditions(&ts_except_tests, &input),
+            Some(true)
+        );
+
+        // Outside such an all_of, a negated file condition means "no file matches"
+        assert!(!evaluate_conditions(&not_tests(), &input, &[]));
+
+        let tests_only = create_test_input(
+            "Add tests",
+            "implement feature",
+            Some(vec!["src/a.test.ts".to_string()]),
+            None,
+        );
+        assert!(!evaluate_conditions(&ts_except_tests, &tests_only, &[]));
+        assert_eq!(
+            pre_evaluate_conditions(&ts_except_tests, &tests_only),
+            Some(false)
+        );
+
+        // The tag is pending for a.ts, even though README.md alone decides the any_of
+        let rules_config = RulesConfig {
+            rules: vec![Rule {
+                id: None,
+                description: None,
+                conditions: RuleConditions::AllOf {
+                    all_of: vec![
+                        file_condition("*.ts"),
+                        not_tests(),
+                        RuleConditions::AnyOf {
+                            any_of: vec![file_condition("*.md"), tag_condition("frontend")],
+                        },
+                    ],
+                },
+                route_to_subagents: vec!["agent".to_string()],
+                suppress_subagents: vec![],
+                enabled: true,
+                mode: RuleMode::Active,
+            }],
+        };
+        let input = create_test_input(
+            "Add tests",
+            "implement feature",
+            Some(vec!["src/a.ts".to_string(), "README.md".to_string()]),
+            None,
+        );
+        assert_eq!(
+            pending_llm_tags(&input, &rules_config),
+            vec!["frontend".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_empty_all_of_never_matches() {
+        let input = create_test_input(
+            "Fix login",
+            "implement feature",
+            Some(vec!["src/a.ts".to_string()]),
+            None,
+        );
+        let empty = RuleConditions::AllOf { all_of: vec![] };
+        assert!(!evaluate_conditions(&empty, &input, &[]));
+        assert_eq!(pre_evaluate_conditions(&empty, &input), Some(false));
+    }
+
+    #[test]
+    fn test_all_of_file_conditions_match_across_files() {
+        let input = create_test_input(
+            "Fix login",
+            "implement feature",
+            Some(vec!["src/a.ts".to_string(), "src/auth.py".to_string()]),
+            None,
+        );
+        let ts_and_auth = RuleConditions::AllOf {
+            all_of: vec![file_condition("*.ts"), file_condition("*auth*")],
+        };
+        assert!(evaluate_conditions(&ts_and_auth, &input, &[]));
+        assert_eq!(pre_evaluate_conditions(&ts_and_auth, &input), Some(true));
+    }
+
+    #[test]
+    fn test_not_and_none_of_conditions() {
+        let input = create_test_input(
+            "Fix bug",
+            "debug",
+            Some(vec!["src/auth.rs".to_string()]),
+            None,
+        );
+        let not = |inner| RuleConditions::Not {
+            not: Box::new(inner),
+        };
+
+        assert!(!evaluate_conditions(
+            &not(file_condition("*.rs")),
+            &input,
+            &[]
+        ));
+        assert!(evaluate_conditions(
+            &not(file_condition("*.ts")),
+            &input,
+            &[]
+        ));
+
+        let none_of = RuleConditions::NoneOf {
+            none_of: vec![file_condition("*.ts"), file_condition("*.md")],
+        };
+        assert!(evaluate_conditions(&none_of, &input, &[]));
+        let none_of = RuleConditions::NoneOf {
+            none_of: vec![file_condition("*.ts"), file_condition("*.rs")],
+        };
+        assert!(!evaluate_conditions(&none_of, &input, &[]));
+
+        // Negated tags are decided once tags are known
+        let not_docs = not(tag_condition("docs"));
+        assert!(evaluate_conditions(&not_docs, &input, &[]));
+        assert!(!evaluate_conditions(
+            &not_docs,
+            &input,
+            &["docs".to_string()]
+        ));
+    }
+
+    #[test]
+    fn test_pre_evaluate_negations() {
+        let input = create_test_input(
+            "Fix bug",
+            "debug",
+            Some(vec!["src/auth.rs".to_string()]),
+            None,
+        );
+
+        let not = RuleConditions::Not {
+            not: Box::new(file_condition("*.rs")),
+        };
+        assert_eq!(pre_evaluate_conditions(&not, &input), Some(false));
+        let not = RuleConditions::Not {
+            not: Box::new(tag_condition("docs")),
+        };
+        assert_eq!(pre_evaluate_conditions(&not, &input), None);
+
+        // none_of with a matched branch is false whatever the tag
+        let none_of = RuleConditions::NoneOf {
+            none_of: vec![file_condition("*.rs"), tag_condition("docs")],
+        };
+        assert_eq!(pre_evaluate_conditions(&none_of, &input), Some(false));
+        let none_of = RuleConditions::NoneOf {
+            none_of: vec![file_condition("*.ts"), tag_condition("docs")],
+        };
+        assert_eq!(pre_evaluate_conditions(&none_of, &input), None);
+
+        let rules_config = RulesConfig {
+            rules: vec![Rule {
+                id: None,
+                description: None,
+                conditions: none_of,
+                route_to_subagents: vec!["agent".to_string()],
+                suppress_subagents: vec![],
+                enabled: true,
+                mode: RuleMode::Active,
+            }],
+        };
+        assert_eq!(pending_llm_tags(&input, &rules_config), vec!["docs"]);
+    }
+
+    #[test]
+    fn test_rule_contains_llm_tags_negations() {
+        let not = RuleConditions::Not {
+            not: Box::new(tag_condition("docs")),
+        };
+        assert!(rule_contains_llm_tags(&not));
+        let none_of = RuleConditions::NoneOf {
+            none_of: vec![file_condition("*.md")],
+        };
+        assert!(!rule_contains_llm_tags(&none_of));
+    }
+
+    #[test]
+    fn test_suppression_rules() {
+        let rules_config = RulesConfig {
+            rules: vec![
+                Rule {
+                    id: None,
+                    description: None,
+                    conditions: branch_condition("^docs/"),
+                    route_to_subagents: vec![],
+                    suppress_subagents: vec!["security-auditor".to_string()],
+                    enabled: true,
+                    mode: RuleMode::Active,
+                },
+                Rule {
+                    id: None,
+                    description: None,
+                    conditions: file_condition("*auth*"),
+                    route_to_subagents: vec![
+                        "security-auditor".to_string(),
+                        "code-reviewer".to_string(),
+                    ],
+                    suppress_subagents: vec![],
+                    enabled: true,
+                    mode: RuleMode::Active,
+                },
+            ],
+        };
+        let files = Some(vec!["src/auth.rs".to_string()]);
+
+        let input = create_test_input("Fix typo", "docs", files.clone(), Some("docs/readme"));
+        assert_eq!(apply_rules(&input, &rules_config), vec!["code-reviewer"]);
+        assert_eq!(
+            suppressed_agents(&input, &[], &rules_config),
+            vec!["security-auditor"]
+        );
+
+        let input = create_test_input("Fix login", "debug", files, Some("main"));
+        assert_eq!(
+            apply_rules(&input, &rules_config),
+            vec!["security-auditor", "code-reviewer"]
+        );
+        assert!(suppressed_agents(&input, &[], &rules_config).is_empty());
+    }
 }
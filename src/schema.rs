@@ -0,0 +1,123 @@
+// JSON Schemas for the config files.
+//
+// Generated from the config types, so fields and their types match what the loader
+// accepts. They are deliberately stricter in one respect: unknown keys are rejected
+// (`additionalProperties: false`) so editors flag typos, while the loader ignores them.
+// The `schema` subcommand and the `get_config_schema` tool serve them, and copies are
+// published in `schemas/` for editors to pick up through a `$schema` key.
+use crate::discovery::ConfigKind;
+use crate::types::{LlmTagConfig, RulesConfig, UserConfig};
+use serde_json::{json, Value};
+
+/// The JSON Schema (draft 2020-12) for one config file
+pub fn config_schema(kind: ConfigKind) -> Value {
+    let mut schema = match kind {
+        ConfigKind::Agents => schemars::schema_for!(UserConfig),
+        ConfigKind::LlmTags => schemars::schema_for!(LlmTagConfig),
+        ConfigKind::Rules => schemars::schema_for!(RulesConfig),
+    }
+    .to_value();
+
+    // Keys the loader handles itself before deserializing
+    if let Some(properties) = schema["properties"].as_object_mut() {
+        properties.insert(
+            "$schema".to_string(),
+            json!({
+                "description": "Schema used by editors; ignored by the router",
+                "type": "string"
+            }),
+        );
+        properties.insert(
+            "extends".to_string(),
+            json!({
+                "description": "Base config files to merge this file on top of, relative to this file",
+                "anyOf": [
+                    {"type": "string"},
+                    {"type": "array", "items": {"type": "string"}}
+                ]
+            }),
+        );
+        if kind == ConfigKind::Rules {
+            properties.insert(
+                "disable_rules".to_string(),
+                json!({
+                    "description": "Ids of inherited rules to drop",
+                    "anyOf": [
+                        {"type": "string"},
+                        {"type": "array", "items": {"type": "string"}}
+                    ]
+                }),
+            );
+        }
+    }
+
+    // A file that extends another may leave out its list and override only some fields
+    // of an inherited entry, so the required fields only apply to standalone files
+    let (list, entry, key) = match kind {
+        ConfigKind::Agents => ("agents", "AgentDefinition", "name"),
+        ConfigKind::LlmTags => ("tags", "LlmTagDefinition", "name"),
+        ConfigKind::Rules => ("rules", "Rule", "id"),
+    };
+    let required = schema.as_object_mut().and_then(|s| s.remove("required"));
+    let entry_required = schema["$defs"][entry]
+        .as_object_mut()
+        .and_then(|s| s.remove("required"));
+    schema["if"] = json!({"required": ["extends"]});
+    schema["then"] = json!({"properties": {list: {"items": {
+        "anyOf": [{"required": [key]}, {"required": entry_required}]
+    }}}});
+    schema["else"] = json!({
+        "required": required,
+        "properties": {list: {"items": {"required": entry_required}}}
+    });
+    schema["title"] = json!(format!("agent-router {} config", kind.file_stem()));
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_published_schemas_are_up_to_date() {
+        for kind in ConfigKind::ALL {
+            let path = format!("schemas/{}.schema.json", kind.file_stem());
+            let published: Value =
+                serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            assert_eq!(
+                published,
+                config_schema(kind),
+                "{} is stale; regenerate it with `agent-router-mcp schema {}`",
+                path,
+                kind.cli_name()
+            );
+        }
+    }
+
+    #[test]
+    fn test_rules_schema_describes_conditions() {
+        let schema = config_schema(ConfigKind::Rules).to_string();
+        for key in [
+            "file_pattern",
+            "llm_tag",
+            "any_of",
+            "none_of",
+            "disable_rules",
+        ] {
+            assert!(schema.contains(&format!("\"{}\"", key)), "missing {}", key);
+        }
+    }
+
+    #[test]
+    fn test_list_keys_accept_a_single_string() {
+        let schema = config_schema(ConfigKind::Rules);
+        for key in ["extends", "disable_rules"] {
+            assert_eq!(
+                schema["properties"][key]["anyOf"][0],
+                json!({"type": "string"}),
+                "{}",
+                key
+            );
+        }
+    }
+}
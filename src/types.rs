@@ -1,3 +1,4 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 // Security: Maximum input sizes to prevent DoS
@@ -160,26 +161,66 @@ pub struct ClassificationResult {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InstructionsResponse {
     pub instructions: Vec<Instruction>,
+    /// True when routing ran without the LLM, so `llm_tag` conditions were not evaluated
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub degraded: bool,
+    /// Why routing was degraded (rules-only mode, or the LLM being unavailable)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub degraded_reason: Option<String>,
+    /// Agents removed from the routing by `suppress_subagents` rules
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suppressed: Vec<Suppression>,
+    /// Shadow-mode rules that matched. They are not reflected in `instructions`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shadow: Vec<ShadowMatch>,
+}
+
+/// A shadow-mode rule that matched, and how it would have changed the routing if active
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShadowMatch {
+    /// The rule's id, its description, or "Rule #N"
+    pub rule: String,
+    /// Every matching condition of the rule
+    pub triggers: Vec<Trigger>,
+    /// Agents the rule would have added to `instructions`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub would_add: Vec<String>,
+    /// Agents the rule would have removed from `instructions`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub would_remove: Vec<String>,
+}
+
+/// An agent that matched some rule but was removed by suppression rules
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Suppression {
+    /// The agent that was removed
+    pub agent: String,
+    /// Every matching condition of every rule that suppressed the agent
+    pub triggers: Vec<Trigger>,
 }
 
 /// A single routing instruction
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Instruction {
-    /// What triggered this routing (rule type + pattern)
-    pub trigger: Trigger,
+    /// Every matching condition of every rule that selected this agent
+    pub triggers: Vec<Trigger>,
     /// Context for executing the instruction
     pub context: InstructionContext,
     /// The agent to route to
     pub route_to_agent: AgentInfo,
 }
 
-/// What triggered this routing
-#[derive(Debug, Serialize, Deserialize)]
+/// A matching condition that contributed to a routing decision
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Trigger {
+    /// The rule the condition belongs to (its id, its description, or "Rule #N")
+    pub rule: String,
     /// The type of trigger (e.g., "file_pattern", "llm_tag", "branch_regex")
     pub name: String,
     /// The specific pattern/value that triggered (e.g., "*.rs", "security-concern")
     pub description: String,
+    /// Files the rule matched
+    pub files: Vec<String>,
 }
 
 /// Context for executing the instruction
@@ -188,9 +229,9 @@ pub struct InstructionContext {
     /// Instructions for the agent when handling this task
     #[serde(skip_serializing_if = "Option::is_none")]
     pub instructions: Option<String>,
-    /// Files that triggered this routing
+    /// Files that triggered this routing, merged across all triggering rules
     pub files: Vec<String>,
-    /// Confidence level (0-100, 100 = deterministic rule match)
+    /// Confidence level (0-100, 100 = deterministic rule match), the highest across rules
     pub confidence: u8,
     /// Priority level (0-100, higher = more important)
     pub priority: u8,
@@ -205,6 +246,87 @@ pub struct AgentInfo {
     pub description: String,
 }
 
+/// Full trace of a routing decision, returned by the `explain_routing` tool
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoutingExplanation {
+    /// The response `get_instructions` would return for the same request
+    pub response: InstructionsResponse,
+    /// What the LLM was asked and answered
+    pub llm: LlmTrace,
+    /// Every enabled rule in the rules config, in order, with its evaluated condition tree
+    pub rules: Vec<RuleTrace>,
+    /// Agents referenced by rules that are not in the response, and why
+    pub dropped_agents: Vec<DroppedAgent>,
+}
+
+/// LLM tagging as it happened while routing a request
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LlmTrace {
+    /// Tags the LLM was asked about (only those that could still change a rule outcome).
+    /// Empty when the LLM was not called.
+    pub requested_tags: Vec<String>,
+    /// Raw reply text of every tagging request, in order
+    pub raw_responses: Vec<String>,
+    /// Tags parsed from the replies, after `min_confidence` filtering
+    pub tags: Vec<TagResult>,
+    /// Why tagging was skipped or failed in a degraded routing mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub degraded_reason: Option<String>,
+}
+
+impl LlmTrace {
+    /// The tags, or `None` when routing was degraded and tags were not evaluated
+    pub fn known_tags(&self) -> Option<&[TagResult]> {
+        self.degraded_reason.is_none().then_some(&self.tags[..])
+    }
+}
+
+/// Evaluation of a single rule
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RuleTrace {
+    /// The rule's id, its description, or "Rule #N"
+    pub rule: String,
+    #[serde(default, skip_serializing_if = "RuleMode::is_active")]
+    pub mode: RuleMode,
+    pub matched: bool,
+    pub conditions: ConditionTrace,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub route_to_subagents: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suppress_subagents: Vec<String>,
+}
+
+/// A node of an evaluated condition tree
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConditionTrace {
+    /// Condition type (e.g. "file_pattern", "llm_tag") or combinator ("any_of", "not", ...)
+    pub kind: String,
+    /// Pattern or tag of a leaf condition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Whether the node holds; for an `all_of` checked per file, for at least one file
+    pub result: bool,
+    /// True when the result depends on `llm_tag` conditions that were not evaluated because
+    /// routing was degraded. The node then does not hold.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub undecided: bool,
+    /// Files matched by a `file_pattern`/`file_regex` leaf
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched_files: Vec<String>,
+    /// The LLM's confidence for a matched `llm_tag` leaf
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<u8>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ConditionTrace>,
+}
+
+/// An agent referenced by the rules that did not make it into the response
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DroppedAgent {
+    pub agent: String,
+    pub reason: String,
+}
+
 /// Result from LLM tag identification with confidence and matched files
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TagResult {
@@ -216,15 +338,19 @@ pub struct TagResult {
     pub files: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct AgentDefinition {
+    /// Unique agent name, as referenced by rules
     pub name: String,
+    /// What the agent does
     pub description: String,
     /// Instructions for the agent when handling this type of task
     #[serde(skip_serializing_if = "Option::is_none")]
     pub instructions: Option<String>,
     /// Priority level (0-100, higher = more important)
     #[serde(default = "default_priority")]
+    #[schemars(range(max = 100))]
     pub priority: u8,
 }
 
@@ -241,9 +367,66 @@ pub enum ModelSource {
     HuggingFace,
 }
 
+/// LLM runtime used for semantic tagging
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Backend {
+    /// Ollama (HTTP API on `OLLAMA_URL`, `ollama` CLI for serve/pull)
+    #[default]
+    Ollama,
+    /// LM Studio (OpenAI-compatible API on `LMSTUDIO_URL`, `lms` CLI for server/models)
+    LmStudio,
+    /// Any OpenAI-compatible server (llama.cpp `llama-server`, vLLM, LocalAI) on `OPENAI_BASE_URL`.
+    /// The server and its model are managed by the user.
+    OpenAi,
+}
+
+impl Backend {
+    /// Parse a backend name as used in the `BACKEND` env var (case-insensitive)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "ollama" => Some(Backend::Ollama),
+            "lmstudio" | "lm-studio" | "lm_studio" => Some(Backend::LmStudio),
+            "openai" | "openai-compatible" | "openai_compatible" => Some(Backend::OpenAi),
+            _ => None,
+        }
+    }
+}
+
+/// How `get_instructions` uses the LLM
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum RoutingMode {
+    /// LLM tagging plus all rules; routing fails if the LLM is unavailable
+    #[default]
+    Hybrid,
+    /// Never call the LLM; only deterministic conditions are evaluated
+    RulesOnly,
+    /// Use the LLM when it is available, otherwise fall back to rules-only routing
+    Auto,
+}
+
+impl RoutingMode {
+    /// Parse a mode name as used in the `ROUTING_MODE` env var (case-insensitive)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "hybrid" => Some(RoutingMode::Hybrid),
+            "rules-only" | "rules_only" | "rules" => Some(RoutingMode::RulesOnly),
+            "auto" => Some(RoutingMode::Auto),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
+    /// Which LLM runtime to use (default: Ollama)
+    pub backend: Backend,
     pub ollama_url: String,
+    /// LM Studio API endpoint (only used with `Backend::LmStudio`)
+    pub lmstudio_url: String,
+    /// Base URL of an OpenAI-compatible server (only used with `Backend::OpenAi`)
+    pub openai_base_url: String,
+    /// Optional bearer token sent to the OpenAI-compatible server
+    pub openai_api_key: Option<String>,
     pub model_name: String,
     pub model_source: ModelSource,
     /// Enable thinking/reasoning mode for supported models (default: true)
@@ -252,15 +435,57 @@ pub struct Config {
     /// Temperature for LLM responses (0.0-1.0, default: 0.1 for tagging, 0.3 for classification)
     /// Lower = more deterministic, higher = more creative
     pub temperature: Option<f32>,
+    /// Ask the backend for schema-constrained JSON tag responses (default: true).
+    /// The legacy numbered-list prompt is used when disabled or unsupported.
+    pub structured_output: bool,
+    /// Whether routing requires, skips, or opportunistically uses the LLM (default: hybrid)
+    pub routing_mode: RoutingMode,
+    /// Fail startup (and config overrides) when rules reference unknown agents or tags
+    /// (default: false, issues are only logged)
+    pub strict_config: bool,
+    /// Reload the configs when their files change (default: true)
+    pub watch_configs: bool,
+    /// How often the config files are checked for changes (default: 2s)
+    pub config_poll_interval: std::time::Duration,
+    /// Directories config discovery starts from, normally the client's MCP roots
+    /// (default: empty, the working directory)
+    pub config_search_roots: Vec<std::path::PathBuf>,
 }
 
 impl Default for Config {
     fn default() -> Self {
+        // Backend: default Ollama, can be changed via BACKEND env var
+        let backend = match std::env::var("BACKEND") {
+            Ok(name) => Backend::parse(&name).unwrap_or_else(|| {
+                eprintln!("⚠️  WARNING: Unknown BACKEND '{}', using ollama", name);
+                Backend::Ollama
+            }),
+            Err(_) => Backend::Ollama,
+        };
+
         let ollama_url =
             std::env::var("OLLAMA_URL").unwrap_or_else(|_| "http://localhost:11434".to_string());
 
-        // Security: Validate that Ollama URL is localhost
-        Self::validate_ollama_url(&ollama_url);
+        let lmstudio_url =
+            std::env::var("LMSTUDIO_URL").unwrap_or_else(|_| "http://localhost:1234".to_string());
+
+        let openai_base_url = std::env::var("OPENAI_BASE_URL")
+            .unwrap_or_else(|_| "http://localhost:8080".to_string());
+
+        let openai_api_key = std::env::var("OPENAI_API_KEY")
+            .ok()
+            .filter(|key| !key.trim().is_empty());
+
+        // Security: Validate that the selected backend's URL is localhost
+        match backend {
+            Backend::Ollama => Self::validate_backend_url("OLLAMA_URL", "Ollama", &ollama_url),
+            Backend::LmStudio => {
+                Self::validate_backend_url("LMSTUDIO_URL", "LM Studio", &lmstudio_url)
+            }
+            Backend::OpenAi => {
+                Self::validate_backend_url("OPENAI_BASE_URL", "OpenAI-compatible", &openai_base_url)
+            }
+        }
 
         // Parse model name and detect source
         // Default model is HuggingFace SmolLM3
@@ -298,12 +523,53 @@ impl Default for Config {
             .and_then(|s| s.parse::<f32>().ok())
             .map(|t| t.clamp(0.0, 1.0));
 
+        // Structured output: default true, can be disabled via STRUCTURED_OUTPUT=false
+        let structured_output = std::env::var("STRUCTURED_OUTPUT")
+            .map(|s| s.to_lowercase() != "false" && s != "0")
+            .unwrap_or(true);
+
+        // Strict config validation: default false, enabled via STRICT_CONFIG=true
+        let strict_config = std::env::var("STRICT_CONFIG")
+            .map(|s| s.to_lowercase() == "true" || s == "1")
+            .unwrap_or(false);
+
+        // Config hot reload: default on, disabled via WATCH_CONFIGS=false
+        let watch_configs = std::env::var("WATCH_CONFIGS")
+            .map(|s| s.to_lowercase() != "false" && s != "0")
+            .unwrap_or(true);
+
+        let config_poll_interval = std::env::var("CONFIG_POLL_INTERVAL_MS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|&ms| ms > 0)
+            .map(std::time::Duration::from_millis)
+            .unwrap_or(crate::reload::DEFAULT_POLL_INTERVAL);
+
+        // Routing mode: default hybrid, can be changed via ROUTING_MODE env var
+        let routing_mode = match std::env::var("ROUTING_MODE") {
+            Ok(name) => RoutingMode::parse(&name).unwrap_or_else(|| {
+                eprintln!("⚠️  WARNING: Unknown ROUTING_MODE '{}', using hybrid", name);
+                RoutingMode::Hybrid
+            }),
+            Err(_) => RoutingMode::Hybrid,
+        };
+
         Self {
+            backend,
             ollama_url,
+            lmstudio_url,
+            openai_base_url,
+            openai_api_key,
             model_name,
             model_source,
             thinking_mode,
             temperature,
+            structured_output,
+            routing_mode,
+            strict_config,
+            watch_configs,
+            config_poll_interval,
+            config_search_roots: Vec::new(),
         }
     }
 }
@@ -335,28 +601,38 @@ impl Config {
         self.thinking_mode && self.supports_thinking()
     }
 
-    /// Validate that Ollama URL is localhost (security check)
-    fn validate_ollama_url(url: &str) {
+    /// Validate that a backend URL is localhost (security check)
+    fn validate_backend_url(env_var: &str, backend_name: &str, url: &str) {
         if !url.starts_with("http://localhost") && !url.starts_with("http://127.0.0.1") {
-            eprintln!("⚠️  WARNING: OLLAMA_URL is not localhost: {}", url);
+            eprintln!("⚠️  WARNING: {} is not localhost: {}", env_var, url);
             eprintln!("   This may expose your system to security risks.");
-            eprintln!("   Only use remote Ollama instances you trust.");
+            eprintln!("   Only use remote {} instances you trust.", backend_name);
         }
     }
 
-    /// Get the effective model name for Ollama API calls.
-    /// For HuggingFace models, this adds the "hf.co/" prefix.
-    /// For Ollama models, this returns the model name as-is.
+    /// Base URL of the selected backend's HTTP API
+    pub fn backend_url(&self) -> &str {
+        match self.backend {
+            Backend::Ollama => &self.ollama_url,
+            Backend::LmStudio => &self.lmstudio_url,
+            Backend::OpenAi => &self.openai_base_url,
+        }
+    }
+
+    /// Get the effective model name for backend API calls.
+    /// For HuggingFace models on Ollama, this adds the "hf.co/" prefix.
+    /// LM Studio uses HuggingFace "user/repo" names directly, so they are returned as-is.
     pub fn effective_model_name(&self) -> String {
-        match self.model_source {
-            ModelSource::HuggingFace => format!("hf.co/{}", self.model_name),
-            ModelSource::Ollama => self.model_name.clone(),
+        match (&self.backend, &self.model_source) {
+            (Backend::Ollama, ModelSource::HuggingFace) => format!("hf.co/{}", self.model_name),
+            _ => self.model_name.clone(),
         }
     }
 }
 
 // User-defined agent configuration
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct UserConfig {
     pub agents: Vec<AgentDefinition>,
 }
@@ -383,7 +659,8 @@ impl UserConfig {
 }
 
 // LLM tag definitions for semantic tagging
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct LlmTagConfig {
     pub tags: Vec<LlmTagDefinition>,
 }
@@ -403,21 +680,38 @@ impl LlmTagConfig {
             if !names.insert(tag.name.clone()) {
                 return Err(format!("Duplicate tag name: {}", tag.name));
             }
+            if let Some(min_confidence) = tag.min_confidence {
+                if min_confidence > 100 {
+                    return Err(format!(
+                        "Tag '{}' has min_confidence {} (must be 0-100)",
+                        tag.name, min_confidence
+                    ));
+                }
+            }
         }
 
         Ok(())
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct LlmTagDefinition {
+    /// Unique tag name, as referenced by `llm_tag` conditions
     pub name: String,
+    /// What the tag means; shown to the LLM
     pub description: String,
+    /// Example requests that should get this tag; shown to the LLM
     pub examples: Vec<String>,
+    /// Optional: minimum LLM confidence (0-100) for this tag to count; lower-scoring tags are dropped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(range(max = 100))]
+    pub min_confidence: Option<u8>,
 }
 
 // Rule-based routing configuration
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct RulesConfig {
     pub rules: Vec<Rule>,
 }
@@ -428,19 +722,40 @@ impl RulesConfig {
             return Err("RulesConfig must contain at least one rule".to_string());
         }
 
-        // Validate each rule has at least one target agent
+        // Check for empty and duplicate ids
+        let mut seen_ids = std::collections::HashMap::new();
         for (idx, rule) in self.rules.iter().enumerate() {
-            if rule.route_to_subagents.is_empty() {
+            let Some(id) = &rule.id else { continue };
+            if id.trim().is_empty() {
+                return Err(format!("Rule #{} has an empty id", idx + 1));
+            }
+            if let Some(first) = seen_ids.insert(id.as_str(), idx) {
                 return Err(format!(
-                    "Rule #{} must route to at least one agent",
+                    "Duplicate rule id '{}' (Rule #{} and Rule #{})",
+                    id,
+                    first + 1,
                     idx + 1
                 ));
             }
+        }
+
+        // Validate each rule has at least one target agent
+        for (idx, rule) in self.rules.iter().enumerate() {
+            if rule.route_to_subagents.is_empty() && rule.suppress_subagents.is_empty() {
+                return Err(format!(
+                    "{} must route to or suppress at least one agent",
+                    rule.display_name(idx)
+                ));
+            }
 
             // Check for empty agent names
-            for agent_name in &rule.route_to_subagents {
+            for agent_name in rule
+                .route_to_subagents
+                .iter()
+                .chain(&rule.suppress_subagents)
+            {
                 if agent_name.trim().is_empty() {
-                    return Err(format!("Rule #{} has empty agent name", idx + 1));
+                    return Err(format!("{} has empty agent name", rule.display_name(idx)));
                 }
             }
         }
@@ -449,32 +764,233 @@ impl RulesConfig {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Rule {
+    /// Stable, unique identifier. Names the rule in responses, errors and logs, and lets
+    /// configs that `extends` this one override or disable the rule.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// What the rule is for; names the rule in responses and errors when it has no `id`
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// When the rule matches: one condition, or `any_of`/`all_of`/`not`/`none_of`
     pub conditions: RuleConditions,
+    /// Agents to route to when this rule matches
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub route_to_subagents: Vec<String>,
+    /// Agents to remove from the routing when this rule matches. Evaluated after all
+    /// additive rules, so a suppression wins regardless of rule order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suppress_subagents: Vec<String>,
+    /// Set to false to keep the rule in the file without evaluating it
+    #[serde(default = "default_enabled", skip_serializing_if = "is_enabled")]
+    pub enabled: bool,
+    /// `shadow` evaluates the rule and reports what it would have changed in the
+    /// response's `shadow` list, without changing the routing
+    #[serde(default, skip_serializing_if = "RuleMode::is_active")]
+    pub mode: RuleMode,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+fn default_enabled() -> bool {
+    true
+}
+
+fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+
+/// Whether a rule's matches change the routing
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleMode {
+    /// Matches route and suppress agents
+    #[default]
+    Active,
+    /// Matches are only reported, to measure a rule's impact before activating it
+    Shadow,
+}
+
+impl RuleMode {
+    pub fn is_active(&self) -> bool {
+        *self == RuleMode::Active
+    }
+}
+
+impl Rule {
+    /// Whether the rule is enabled and its matches change the routing
+    pub fn is_active(&self) -> bool {
+        self.enabled && self.mode.is_active()
+    }
+
+    /// Whether the rule is enabled and in shadow mode
+    pub fn is_shadow(&self) -> bool {
+        self.enabled && self.mode == RuleMode::Shadow
+    }
+
+    /// Name used for this rule in responses, error messages and logs: its id, else its
+    /// description, else its position. Only the id stays the same when rules are inserted.
+    pub fn display_name(&self, index: usize) -> String {
+        self.id
+            .clone()
+            .or_else(|| self.description.clone())
+            .unwrap_or_else(|| format!("Rule #{}", index + 1))
+    }
+}
+
+// Deserialized by key rather than as an untagged enum, so a bad condition is reported
+// by name instead of as "data did not match any variant"
+#[derive(Debug, Serialize, Clone, PartialEq, JsonSchema)]
 #[serde(untagged)]
+#[schemars(deny_unknown_fields)]
 pub enum RuleConditions {
     Single(Condition),
-    AnyOf { any_of: Vec<RuleConditions> },
-    AllOf { all_of: Vec<RuleConditions> },
+    /// Matches when any of the inner conditions match
+    AnyOf {
+        any_of: Vec<RuleConditions>,
+    },
+    /// Matches when all of the inner conditions match
+    AllOf {
+        all_of: Vec<RuleConditions>,
+    },
+    /// Matches when the inner condition does not
+    Not {
+        not: Box<RuleConditions>,
+    },
+    /// Matches when none of the inner conditions match
+    NoneOf {
+        none_of: Vec<RuleConditions>,
+    },
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Condition {
+    /// Glob matched against the associated files (e.g. `*.rs`, `src/**/*.ts`)
     FilePattern(String),
+    /// Regex matched against the associated files
     FileRegex(String),
+    /// Regex matched against the task, intent and original prompt
     PromptRegex(String),
+    /// Regex matched against the git branch
     BranchRegex(String),
+    /// Tag the LLM must assign, as defined in the tag config
     LlmTag(String),
 }
 
+/// Every key a condition object can have
+pub const CONDITION_KEYS: [&str; 9] = [
+    "file_pattern",
+    "file_regex",
+    "prompt_regex",
+    "branch_regex",
+    "llm_tag",
+    "any_of",
+    "all_of",
+    "not",
+    "none_of",
+];
+
+impl<'de> Deserialize<'de> for RuleConditions {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(RuleConditionsVisitor)
+    }
+}
+
+struct RuleConditionsVisitor;
+
+impl<'de> serde::de::Visitor<'de> for RuleConditionsVisitor {
+    type Value = RuleConditions;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "a condition object with one of the keys {}",
+            CONDITION_KEYS.join(", ")
+        )
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        use serde::de::Error;
+
+        let Some(key) = map.next_key::<String>()? else {
+            return Err(A::Error::custom(format!(
+                "empty condition, expected one of the keys {}",
+                CONDITION_KEYS.join(", ")
+            )));
+        };
+        let conditions = match key.as_str() {
+            "file_pattern" => RuleConditions::Single(Condition::FilePattern(map.next_value()?)),
+            "file_regex" => RuleConditions::Single(Condition::FileRegex(map.next_value()?)),
+            "prompt_regex" => RuleConditions::Single(Condition::PromptRegex(map.next_value()?)),
+            "branch_regex" => RuleConditions::Single(Condition::BranchRegex(map.next_value()?)),
+            "llm_tag" => RuleConditions::Single(Condition::LlmTag(map.next_value()?)),
+            "any_of" => RuleConditions::AnyOf {
+                any_of: map.next_value()?,
+            },
+            "all_of" => RuleConditions::AllOf {
+                all_of: map.next_value()?,
+            },
+            "not" => RuleConditions::Not {
+                not: map.next_value()?,
+            },
+            "none_of" => RuleConditions::NoneOf {
+                none_of: map.next_value()?,
+            },
+            unknown => return Err(A::Error::custom(unknown_condition_key(unknown))),
+        };
+
+        if let Some(extra) = map.next_key::<String>()? {
+            return Err(A::Error::custom(format!(
+                "condition has more than one key (`{}` and `{}`); combine conditions with all_of or any_of",
+                key, extra
+            )));
+        }
+        Ok(conditions)
+    }
+}
+
+/// Error message for a condition key that does not exist, suggesting the closest valid one
+fn unknown_condition_key(key: &str) -> String {
+    let closest = CONDITION_KEYS
+        .iter()
+        .map(|valid| (strsim::levenshtein(key, valid), valid))
+        .min()
+        .filter(|(distance, valid)| *distance <= valid.len() / 3)
+        .map(|(_, valid)| format!(" (did you mean `{}`?)", valid))
+        .unwrap_or_default();
+    format!(
+        "unknown condition key `{}`{}; valid keys are {}",
+        key,
+        closest,
+        CONDITION_KEYS.join(", ")
+    )
+}
+
+impl Condition {
+    /// The condition's key in the rules config (e.g. "file_pattern")
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Condition::FilePattern(_) => "file_pattern",
+            Condition::FileRegex(_) => "file_regex",
+            Condition::PromptRegex(_) => "prompt_regex",
+            Condition::BranchRegex(_) => "branch_regex",
+            Condition::LlmTag(_) => "llm_tag",
+        }
+    }
+
+    /// The pattern or tag name
+    pub fn value(&self) -> &str {
+        match self {
+            Condition::FilePattern(v)
+            | Condition::FileRegex(v)
+            | Condition::PromptRegex(v)
+            | Condition::BranchRegex(v)
+            | Condition::LlmTag(v) => v,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -610,6 +1126,57 @@ mod tests {
         assert!(config.is_ok());
     }
 
+    #[test]
+    fn test_condition_errors_name_the_key() {
+        let err = serde_json::from_str::<RuleConditions>(r#"{"file_patern": "*.rs"}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("unknown condition key `file_patern`"),
+            "{}",
+            err
+        );
+        assert!(err.contains("did you mean `file_pattern`?"), "{}", err);
+        assert!(err.contains(&CONDITION_KEYS.join(", ")), "{}", err);
+
+        let err = serde_json::from_str::<RuleConditions>(r#"{"banana": "*.rs"}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(!err.contains("did you mean"), "{}", err);
+
+        let err = serde_json::from_str::<RuleConditions>(
+            r#"{"file_pattern": "*.rs", "llm_tag": "rust"}"#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("more than one key"), "{}", err);
+    }
+
+    #[test]
+    fn test_rules_config_with_negations() {
+        let json = r#"{
+            "rules": [
+                {
+                    "conditions": {
+                        "all_of": [
+                            {"file_pattern": "src/**"},
+                            {"not": {"file_pattern": "**/generated/**"}},
+                            {"none_of": [{"branch_regex": "^release/"}, {"llm_tag": "docs"}]}
+                        ]
+                    },
+                    "route_to_subagents": ["code-reviewer"]
+                }
+            ]
+        }"#;
+
+        let config: RulesConfig = serde_json::from_str(json).unwrap();
+        let RuleConditions::AllOf { all_of } = &config.rules[0].conditions else {
+            panic!("expected all_of");
+        };
+        assert!(matches!(all_of[1], RuleConditions::Not { .. }));
+        assert!(matches!(&all_of[2], RuleConditions::NoneOf { none_of } if none_of.len() == 2));
+    }
+
     #[test]
     fn test_llm_tag_config() {
         let json = r#"{
@@ -778,6 +1345,25 @@ mod tests {
         assert!(config.is_ok());
     }
 
+    #[test]
+    fn test_llm_tag_min_confidence() {
+        let json = r#"{
+            "tags": [
+                {"name": "security", "description": "Security", "examples": [], "min_confidence": 70},
+                {"name": "testing", "description": "Tests", "examples": []}
+            ]
+        }"#;
+
+        let config: LlmTagConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.tags[0].min_confidence, Some(70));
+        assert_eq!(config.tags[1].min_confidence, None);
+        assert!(config.validate().is_ok());
+
+        let json = r#"{"tags": [{"name": "security", "description": "S", "examples": [], "min_confidence": 150}]}"#;
+        let config: LlmTagConfig = serde_json::from_str(json).unwrap();
+        assert!(config.validate().unwrap_err().contains("min_confidence"));
+    }
+
     #[test]
     fn test_rule_without_description() {
         let json = r#"{
@@ -795,6 +1381,68 @@ mod tests {
         assert!(config.rules[0].description.is_none());
     }
 
+    #[test]
+    fn test_rule_ids() {
+        let config: RulesConfig = serde_json::from_str(
+            r#"{"rules": [
+                {"id": "typescript", "description": "TypeScript files",
+                 "conditions": {"file_pattern": "*.ts"}, "route_to_subagents": ["ts-agent"]},
+                {"description": "Rust files", "conditions": {"file_pattern": "*.rs"},
+                 "route_to_subagents": ["rust-agent"]},
+                {"conditions": {"file_pattern": "*.py"}}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.rules[0].display_name(0), "typescript");
+        assert_eq!(config.rules[1].display_name(1), "Rust files");
+        assert_eq!(config.rules[2].display_name(2), "Rule #3");
+
+        let mut config = config;
+        config.rules[2].id = Some("typescript".to_string());
+        assert_eq!(
+            config.validate().unwrap_err(),
+            "Duplicate rule id 'typescript' (Rule #1 and Rule #3)"
+        );
+
+        config.rules[2].id = Some("python".to_string());
+        assert_eq!(
+            config.validate().unwrap_err(),
+            "python must route to or suppress at least one agent"
+        );
+
+        config.rules[2].id = Some(" ".to_string());
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .contains("Rule #3 has an empty id"));
+    }
+
+    #[test]
+    fn test_suppression_rule_validation() {
+        let config: RulesConfig = serde_json::from_str(
+            r#"{"rules": [
+                {"conditions": {"branch_regex": "^docs/"}, "suppress_subagents": ["security-auditor"]}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(config.rules[0].route_to_subagents.is_empty());
+        assert!(config.validate().is_ok());
+
+        let config: RulesConfig =
+            serde_json::from_str(r#"{"rules": [{"conditions": {"file_pattern": "*.ts"}}]}"#)
+                .unwrap();
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .contains("must route to or suppress"));
+
+        let config: RulesConfig = serde_json::from_str(
+            r#"{"rules": [{"conditions": {"file_pattern": "*.ts"}, "suppress_subagents": [" "]}]}"#,
+        )
+        .unwrap();
+        assert!(config.validate().unwrap_err().contains("empty agent name"));
+    }
+
     #[test]
     fn test_multiple_route_to_subagents() {
         let json = r#"{
@@ -963,8 +1611,16 @@ mod tests {
         std::env::remove_var("MODEL_NAME");
         std::env::remove_var("MODEL_SOURCE");
         std::env::remove_var("AUTO_START_OLLAMA");
+        std::env::remove_var("BACKEND");
+        std::env::remove_var("STRUCTURED_OUTPUT");
+        std::env::remove_var("ROUTING_MODE");
+        std::env::remove_var("STRICT_CONFIG");
 
         let config = Config::default();
+        assert_eq!(config.backend, Backend::Ollama);
+        assert!(config.structured_output);
+        assert!(!config.strict_config);
+        assert_eq!(config.routing_mode, RoutingMode::Hybrid);
         assert_eq!(config.ollama_url, "http://localhost:11434");
         assert_eq!(config.model_name, "ggml-org/SmolLM3-3B-GGUF");
         // Default model is HuggingFace, so effective_model_name adds the prefix
@@ -1171,4 +1827,141 @@ mod tests {
         // Cleanup
         std::env::remove_var("AUTO_START_OLLAMA");
     }
+
+    #[test]
+    fn test_backend_parse() {
+        assert_eq!(Backend::parse("ollama"), Some(Backend::Ollama));
+        assert_eq!(Backend::parse(" Ollama "), Some(Backend::Ollama));
+        assert_eq!(Backend::parse("unknown"), None);
+    }
+
+    #[test]
+    #[serial]
+    fn test_config_unknown_backend_falls_back_to_ollama() {
+        std::env::set_var("BACKEND", "not-a-backend");
+
+        let config = Config::default();
+        assert_eq!(config.backend, Backend::Ollama);
+
+        // Cleanup
+        std::env::remove_var("BACKEND");
+    }
+
+    #[test]
+    fn test_backend_parse_lmstudio() {
+        assert_eq!(Backend::parse("lmstudio"), Some(Backend::LmStudio));
+        assert_eq!(Backend::parse("LM-Studio"), Some(Backend::LmStudio));
+    }
+
+    #[test]
+    #[serial]
+    fn test_config_with_lmstudio_backend() {
+        std::env::set_var("BACKEND", "lmstudio");
+        std::env::set_var("LMSTUDIO_URL", "http://localhost:4321");
+        std::env::set_var("MODEL_NAME", "hf.co/unsloth/SmolLM3-3B-128K-GGUF");
+        std::env::remove_var("MODEL_SOURCE");
+
+        let config = Config::default();
+        assert_eq!(config.backend, Backend::LmStudio);
+        assert_eq!(config.backend_url(), "http://localhost:4321");
+        // LM Studio takes HuggingFace names without the hf.co/ prefix
+        assert_eq!(
+            config.effective_model_name(),
+            "unsloth/SmolLM3-3B-128K-GGUF"
+        );
+
+        // Cleanup
+        std::env::remove_var("BACKEND");
+        std::env::remove_var("LMSTUDIO_URL");
+        std::env::remove_var("MODEL_NAME");
+    }
+
+    #[test]
+    fn test_backend_parse_openai() {
+        assert_eq!(Backend::parse("openai"), Some(Backend::OpenAi));
+        assert_eq!(Backend::parse("OpenAI-Compatible"), Some(Backend::OpenAi));
+    }
+
+    #[test]
+    #[serial]
+    fn test_config_with_openai_backend() {
+        std::env::set_var("BACKEND", "openai");
+        std::env::set_var("OPENAI_BASE_URL", "http://localhost:8000");
+        std::env::set_var("OPENAI_API_KEY", "secret");
+        std::env::set_var("MODEL_NAME", "hf.co/unsloth/SmolLM3-3B-128K-GGUF");
+        std::env::remove_var("MODEL_SOURCE");
+
+        let config = Config::default();
+        assert_eq!(config.backend, Backend::OpenAi);
+        assert_eq!(config.backend_url(), "http://localhost:8000");
+        assert_eq!(config.openai_api_key.as_deref(), Some("secret"));
+        assert_eq!(
+            config.effective_model_name(),
+            "unsloth/SmolLM3-3B-128K-GGUF"
+        );
+
+        // An empty key means no Authorization header
+        std::env::set_var("OPENAI_API_KEY", "");
+        assert_eq!(Config::default().openai_api_key, None);
+
+        // Cleanup
+        std::env::remove_var("BACKEND");
+        std::env::remove_var("OPENAI_BASE_URL");
+        std::env::remove_var("OPENAI_API_KEY");
+        std::env::remove_var("MODEL_NAME");
+    }
+
+    #[test]
+    fn test_routing_mode_parse() {
+        assert_eq!(RoutingMode::parse("hybrid"), Some(RoutingMode::Hybrid));
+        assert_eq!(
+            RoutingMode::parse("Rules-Only"),
+            Some(RoutingMode::RulesOnly)
+        );
+        assert_eq!(
+            RoutingMode::parse("rules_only"),
+            Some(RoutingMode::RulesOnly)
+        );
+        assert_eq!(RoutingMode::parse(" auto "), Some(RoutingMode::Auto));
+        assert_eq!(RoutingMode::parse("llm"), None);
+    }
+
+    #[test]
+    #[serial]
+    fn test_config_routing_mode_from_env() {
+        std::env::set_var("ROUTING_MODE", "auto");
+        assert_eq!(Config::default().routing_mode, RoutingMode::Auto);
+
+        std::env::set_var("ROUTING_MODE", "bogus");
+        assert_eq!(Config::default().routing_mode, RoutingMode::Hybrid);
+
+        // Cleanup
+        std::env::remove_var("ROUTING_MODE");
+    }
+
+    #[test]
+    fn test_instructions_response_degraded_serialization() {
+        let normal = InstructionsResponse {
+            instructions: vec![],
+            degraded: false,
+            degraded_reason: None,
+            suppressed: vec![],
+            shadow: vec![],
+        };
+        assert_eq!(
+            serde_json::to_value(&normal).unwrap(),
+            serde_json::json!({"instructions": []})
+        );
+
+        let degraded = InstructionsResponse {
+            instructions: vec![],
+            degraded: true,
+            degraded_reason: Some("LLM unavailable".to_string()),
+            suppressed: vec![],
+            shadow: vec![],
+        };
+        let json = serde_json::to_value(&degraded).unwrap();
+        assert_eq!(json["degraded"], true);
+        assert_eq!(json["degraded_reason"], "LLM unavailable");
+    }
 }
@@ -0,0 +1,292 @@
+// Cross-config validation.
+//
+// Each config file is checked on its own when it is loaded (`RulesConfig::validate` and
+// friends). This module checks that the agent, tag and rules configs agree with each
+// other, so a rule naming a missing agent or tag is reported instead of never matching.
+use crate::types::{Condition, LlmTagConfig, RuleConditions, RulesConfig, UserConfig};
+use anyhow::Result;
+use std::fmt;
+use tracing::warn;
+
+/// How serious a `ConfigIssue` is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Routing silently loses a match; fails startup with `STRICT_CONFIG`
+    Error,
+    /// Probably unintended, but routing still works as written
+    Warning,
+}
+
+/// A disagreement between the agent, tag and rules configs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigIssue {
+    /// A rule routes to or suppresses an agent missing from the agent config
+    UnknownAgent {
+        rule: String,
+        rule_index: usize,
+        agent: String,
+    },
+    /// An `llm_tag` condition names a tag missing from the tag config
+    UnknownTag {
+        rule: String,
+        rule_index: usize,
+        tag: String,
+        /// JSON pointer to the first condition using the tag, relative to the rule's
+        /// `conditions`
+        pointer: String,
+    },
+    /// An agent that no rule routes to or suppresses
+    UnreferencedAgent { agent: String },
+    /// A tag that no `llm_tag` condition uses
+    UnusedTag { tag: String },
+}
+
+impl ConfigIssue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            ConfigIssue::UnknownAgent { .. } | ConfigIssue::UnknownTag { .. } => Severity::Error,
+            ConfigIssue::UnreferencedAgent { .. } | ConfigIssue::UnusedTag { .. } => {
+                Severity::Warning
+            }
+        }
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigIssue::UnknownAgent { rule, agent, .. } => write!(
+                f,
+                "{} references agent '{}' which is not defined in the agent config",
+                rule, agent
+            ),
+            ConfigIssue::UnknownTag { rule, tag, .. } => write!(
+                f,
+                "{} uses llm_tag '{}' which is not defined in the tag config",
+                rule, tag
+            ),
+            ConfigIssue::UnreferencedAgent { agent } => {
+                write!(f, "Agent '{}' is not referenced by any rule", agent)
+            }
+            ConfigIssue::UnusedTag { tag } => {
+                write!(f, "Tag '{}' is not used by any rule", tag)
+            }
+        }
+    }
+}
+
+/// Cross-check the three configs. Issues are ordered: unknown agents and tags in rule
+/// order, then unreferenced agents, then unused tags.
+pub fn validate_configs(
+    user_config: &UserConfig,
+    tag_config: &LlmTagConfig,
+    rules_config: &RulesConfig,
+) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    let mut referenced_agents: Vec<&str> = Vec::new();
+    let mut used_tags: Vec<String> = Vec::new();
+
+    for (idx, rule) in rules_config.rules.iter().enumerate() {
+        for agent in rule
+            .route_to_subagents
+            .iter()
+            .chain(&rule.suppress_subagents)
+        {
+            if !user_config.agents.iter().any(|a| &a.name == agent) {
+                issues.push(ConfigIssue::UnknownAgent {
+                    rule: rule.display_name(idx),
+                    rule_index: idx,
+                    agent: agent.clone(),
+                });
+            }
+            referenced_agents.push(agent);
+        }
+
+        let mut rule_tags = Vec::new();
+        collect_llm_tags(&rule.conditions, "", &mut rule_tags);
+        for (tag, pointer) in rule_tags {
+            if !tag_config.tags.iter().any(|t| t.name == tag) {
+                issues.push(ConfigIssue::UnknownTag {
+                    rule: rule.display_name(idx),
+                    rule_index: idx,
+                    tag: tag.clone(),
+                    pointer,
+                });
+            }
+            used_tags.push(tag);
+        }
+    }
+
+    for agent in &user_config.agents {
+        if !referenced_agents.contains(&agent.name.as_str()) {
+            issues.push(ConfigIssue::UnreferencedAgent {
+                agent: agent.name.clone(),
+            });
+        }
+    }
+
+    for tag in &tag_config.tags {
+        if !used_tags.contains(&tag.name) {
+            issues.push(ConfigIssue::UnusedTag {
+                tag: tag.name.clone(),
+            });
+        }
+    }
+
+    issues
+}
+
+/// Validate the configs and log every issue. In strict mode any `Severity::Error`
+/// issue is returned as an error instead.
+pub fn check_configs(
+    user_config: &UserConfig,
+    tag_config: &LlmTagConfig,
+    rules_config: &RulesConfig,
+    strict: bool,
+) -> Result<Vec<ConfigIssue>> {
+    let issues = validate_configs(user_config, tag_config, rules_config);
+
+    let errors: Vec<String> = issues
+        .iter()
+        .filter(|i| i.severity() == Severity::Error)
+        .map(|i| i.to_string())
+        .collect();
+    if strict && !errors.is_empty() {
+        anyhow::bail!(
+            "Config validation failed (STRICT_CONFIG):\n  {}",
+            errors.join("\n  ")
+        );
+    }
+
+    for issue in &issues {
+        warn!("Config: {}", issue);
+    }
+
+    Ok(issues)
+}
+
+/// Every tag used by an `llm_tag` condition, with the pointer to its first use
+fn collect_llm_tags(conditions: &RuleConditions, pointer: &str, tags: &mut Vec<(String, String)>) {
+    match conditions {
+        RuleConditions::Single(Condition::LlmTag(tag)) => {
+            if !tags.iter().any(|(t, _)| t == tag) {
+                tags.push((tag.clone(), format!("{}/llm_tag", pointer)));
+            }
+        }
+        RuleConditions::Single(_) => {}
+        RuleConditions::AnyOf { any_of: children } => {
+            for (i, c) in children.iter().enumerate() {
+                collect_llm_tags(c, &format!("{}/any_of/{}", pointer, i), tags);
+            }
+        }
+        RuleConditions::AllOf { all_of: children } => {
+            for (i, c) in children.iter().enumerate() {
+                collect_llm_tags(c, &format!("{}/all_of/{}", pointer, i), tags);
+            }
+        }
+        RuleConditions::NoneOf { none_of: children } => {
+            for (i, c) in children.iter().enumerate() {
+                collect_llm_tags(c, &format!("{}/none_of/{}", pointer, i), tags);
+            }
+        }
+        RuleConditions::Not { not } => collect_llm_tags(not, &format!("{}/not", pointer), tags),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configs() -> (UserConfig, LlmTagConfig, RulesConfig) {
+        let user_config = serde_json::from_str(
+            r#"{"agents": [
+                {"name": "rust-reviewer", "description": "Rust"},
+                {"name": "security-auditor", "description": "Security"},
+                {"name": "planner", "description": "Planning"}
+            ]}"#,
+        )
+        .unwrap();
+        let tag_config = serde_json::from_str(
+            r#"{"tags": [
+                {"name": "security", "description": "Security", "examples": []},
+                {"name": "performance", "description": "Performance", "examples": []}
+            ]}"#,
+        )
+        .unwrap();
+        let rules_config = serde_json::from_str(
+            r#"{"rules": [
+                {"conditions": {"file_pattern": "*.rs"}, "route_to_subagents": ["rust-reviewer"]},
+                {"description": "Security review",
+                 "conditions": {"any_of": [{"llm_tag": "security"}, {"not": {"llm_tag": "secrets"}}]},
+                 "route_to_subagents": ["security-auditor", "secrets-scanner"]},
+                {"conditions": {"branch_regex": "^docs/"}, "suppress_subagents": ["rust-reviewer"]}
+            ]}"#,
+        )
+        .unwrap();
+        (user_config, tag_config, rules_config)
+    }
+
+    #[test]
+    fn test_validate_configs_reports_all_issue_kinds() {
+        let (user_config, tag_config, rules_config) = configs();
+
+        let issues = validate_configs(&user_config, &tag_config, &rules_config);
+        assert_eq!(
+            issues,
+            vec![
+                ConfigIssue::UnknownAgent {
+                    rule: "Security review".to_string(),
+                    rule_index: 1,
+                    agent: "secrets-scanner".to_string(),
+                },
+                ConfigIssue::UnknownTag {
+                    rule: "Security review".to_string(),
+                    rule_index: 1,
+                    tag: "secrets".to_string(),
+                    pointer: "/any_of/1/not/llm_tag".to_string(),
+                },
+                ConfigIssue::UnreferencedAgent {
+                    agent: "planner".to_string(),
+                },
+                ConfigIssue::UnusedTag {
+                    tag: "performance".to_string(),
+                },
+            ]
+        );
+        assert_eq!(issues[0].severity(), Severity::Error);
+        assert_eq!(issues[3].severity(), Severity::Warning);
+        assert_eq!(
+            issues[0].to_string(),
+            "Security review references agent 'secrets-scanner' which is not defined in the agent config"
+        );
+    }
+
+    #[test]
+    fn test_check_configs_strict_fails_only_on_errors() {
+        let (user_config, tag_config, mut rules_config) = configs();
+
+        assert_eq!(
+            check_configs(&user_config, &tag_config, &rules_config, false)
+                .unwrap()
+                .len(),
+            4
+        );
+        let err = check_configs(&user_config, &tag_config, &rules_config, true).unwrap_err();
+        assert!(err.to_string().contains("secrets-scanner"));
+        assert!(err.to_string().contains("'secrets'"));
+
+        // Unreferenced agents and unused tags are only warnings
+        rules_config.rules.remove(1);
+        assert!(check_configs(&user_config, &tag_config, &rules_config, true).is_ok());
+    }
+
+    #[test]
+    fn test_default_configs_have_no_errors() {
+        let issues = validate_configs(
+            &crate::rules::default_user_config().unwrap(),
+            &crate::rules::default_llm_tag_config().unwrap(),
+            &crate::rules::default_rules_config().unwrap(),
+        );
+        assert!(issues.iter().all(|i| i.severity() == Severity::Warning));
+    }
+}
@@ -21,13 +21,17 @@ async fn test_list_tools() {
         .await
         .expect("Failed to list tools");
 
-    // Should return 2 tools (init_llm and get_instructions)
-    assert_eq!(result.tools.len(), 2);
+    // Should return 5 tools (init_llm, get_instructions, explain_routing, get_config_schema
+    // and reload_config)
+    assert_eq!(result.tools.len(), 5);
 
     // Check tool names
     let tool_names: Vec<String> = result.tools.iter().map(|t| t.name.clone()).collect();
     assert!(tool_names.contains(&"init_llm".to_string()));
     assert!(tool_names.contains(&"get_instructions".to_string()));
+    assert!(tool_names.contains(&"explain_routing".to_string()));
+    assert!(tool_names.contains(&"get_config_schema".to_string()));
+    assert!(tool_names.contains(&"reload_config".to_string()));
 
     // Print schema for debugging
     for tool in &result.tools {
@@ -151,6 +155,453 @@ async fn test_init_llm_tool() {
     }
 }
 
+#[tokio::test]
+async fn test_init_llm_with_openai_backend_skips_model_steps() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "object": "list",
+            "data": [{"id": "local-model", "object": "model"}]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let handler = create_test_handler();
+    handler.state.lock().await.config = Config {
+        backend: Backend::OpenAi,
+        openai_base_url: mock_server.uri(),
+        openai_api_key: None,
+        ..Config::default()
+    };
+    let runtime = create_mock_runtime();
+
+    let params = CallToolRequestParams {
+        name: "init_llm".to_string(),
+        arguments: Some(json!({}).as_object().unwrap().clone()),
+        meta: None,
+        task: None,
+    };
+
+    let result = handler
+        .handle_call_tool_request(params, runtime)
+        .await
+        .expect("init_llm failed");
+    let text = match &result.content[0] {
+        ContentBlock::TextContent(text) => text.text.clone(),
+        other => panic!("Expected text content, got {:?}", other),
+    };
+    let output: serde_json::Value = serde_json::from_str(&text).unwrap();
+
+    assert_eq!(output["success"], true);
+    let steps: Vec<&str> = output["steps_performed"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s.as_str().unwrap())
+        .collect();
+    assert_eq!(
+        steps,
+        vec![
+            "OpenAI-compatible server already running",
+            "Model download not applicable for OpenAI-compatible server",
+            "Model loading not applicable for OpenAI-compatible server",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_get_instructions_rules_only_without_llm() {
+    let handler = create_test_handler();
+    handler.state.lock().await.config = Config {
+        backend: Backend::Ollama,
+        // Nothing listens here; rules-only routing must not need it
+        ollama_url: "http://localhost:1".to_string(),
+        routing_mode: RoutingMode::RulesOnly,
+        ..Config::default()
+    };
+    let runtime = create_mock_runtime();
+
+    let params = CallToolRequestParams {
+        name: "get_instructions".to_string(),
+        arguments: Some(
+            json!({
+                "task": "Fix the parser",
+                "intent": "help debug an issue",
+                "associated_files": ["src/main.rs"]
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ),
+        meta: None,
+        task: None,
+    };
+
+    let result = handler
+        .handle_call_tool_request(params, runtime)
+        .await
+        .expect("get_instructions failed");
+    let text = match &result.content[0] {
+        ContentBlock::TextContent(text) => text.text.clone(),
+        other => panic!("Expected text content, got {:?}", other),
+    };
+    let output: serde_json::Value = serde_json::from_str(&text).unwrap();
+
+    assert_eq!(output["degraded"], true, "Unexpected response: {}", text);
+    let agents: Vec<&str> = output["instructions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["route_to_agent"]["name"].as_str().unwrap())
+        .collect();
+    assert!(agents.contains(&"language-reviewer-rust"));
+}
+
+#[test]
+fn test_route_cli_without_llm() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_agent-router-mcp"))
+        .args([
+            "route",
+            "--task",
+            "Fix the parser",
+            "--intent",
+            "help debug an issue",
+            "--file",
+            "src/main.rs",
+            "--rules",
+            "config/rules.json",
+            "--no-llm",
+        ])
+        .output()
+        .expect("failed to run agent-router-mcp");
+    assert!(
+        output.status.success(),
+        "route failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["degraded"], true);
+    assert_eq!(
+        response["instructions"][0]["route_to_agent"]["name"],
+        "language-reviewer-rust"
+    );
+}
+
+#[test]
+fn test_route_cli_flags_bypass_broken_discovered_config() {
+    let repo =
+        std::env::temp_dir().join(format!("agent-router-route-flags-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&repo);
+    std::fs::create_dir_all(repo.join(".git")).unwrap();
+    std::fs::create_dir_all(repo.join(".agent-router")).unwrap();
+    std::fs::write(repo.join(".agent-router/rules.json"), "{not json").unwrap();
+    let rules = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("config/rules.json");
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_agent-router-mcp"))
+        .args([
+            "route", "--task", "fix", "--intent", "fix bug", "--file", "src/a.rs",
+        ])
+        .arg("--rules")
+        .arg(&rules)
+        .arg("--no-llm")
+        .current_dir(&repo)
+        .env_remove("AGENTS_CONFIG_PATH")
+        .env_remove("RULES_CONFIG_PATH")
+        .env_remove("LLM_TAGS_CONFIG_PATH")
+        .env("XDG_CONFIG_HOME", repo.join("no-user-config"))
+        .output()
+        .expect("failed to run agent-router-mcp");
+    let _ = std::fs::remove_dir_all(&repo);
+    assert!(
+        output.status.success(),
+        "route failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        response["instructions"][0]["route_to_agent"]["name"],
+        "language-reviewer-rust"
+    );
+}
+
+#[test]
+fn test_dump_config_cli_merges_repository_config() {
+    let repo = std::env::temp_dir().join(format!(
+        "agent-router-dump-config-test-{}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&repo);
+    std::fs::create_dir_all(repo.join(".git")).unwrap();
+    std::fs::create_dir_all(repo.join(".agent-router")).unwrap();
+    std::fs::create_dir_all(repo.join("src")).unwrap();
+    let base_rules = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("config/rules.json");
+    std::fs::write(
+        repo.join(".agent-router/rules.json"),
+        json!({
+            "extends": base_rules,
+            "rules": [{"id": "changelog", "conditions": {"file_pattern": "CHANGELOG.md"}, "route_to_subagents": ["docs-reviewer"]}]
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_agent-router-mcp"))
+        .arg("dump-config")
+        .current_dir(repo.join("src"))
+        .env_remove("AGENTS_CONFIG_PATH")
+        .env_remove("RULES_CONFIG_PATH")
+        .env_remove("LLM_TAGS_CONFIG_PATH")
+        .env("XDG_CONFIG_HOME", repo.join("no-user-config"))
+        .output()
+        .expect("failed to run agent-router-mcp");
+    assert!(
+        output.status.success(),
+        "dump-config failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let dump: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(dump["sources"]["rules"]["source"], "repository");
+    assert_eq!(dump["sources"]["agents"]["source"], "bundled");
+    let base: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&base_rules).unwrap()).unwrap();
+    let rules = dump["rules"].as_array().unwrap();
+    assert_eq!(rules.len(), base["rules"].as_array().unwrap().len() + 1);
+    assert_eq!(rules.last().unwrap()["id"], "changelog");
+    let _ = std::fs::remove_dir_all(&repo);
+}
+
+#[test]
+fn test_lint_cli_lints_discovered_configs() {
+    let repo = std::env::temp_dir().join(format!(
+        "agent-router-lint-discovery-test-{}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&repo);
+    std::fs::create_dir_all(repo.join(".git")).unwrap();
+    std::fs::create_dir_all(repo.join(".agent-router")).unwrap();
+    std::fs::write(
+        repo.join(".agent-router/rules.json"),
+        json!({
+            "rules": [{"id": "broken", "conditions": {"file_pattern": "src/[*.rs"}, "route_to_subagents": ["language-reviewer-rust"]}]
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_agent-router-mcp"))
+        .arg("lint")
+        .current_dir(&repo)
+        .env_remove("AGENTS_CONFIG_PATH")
+        .env_remove("RULES_CONFIG_PATH")
+        .env_remove("LLM_TAGS_CONFIG_PATH")
+        .env("XDG_CONFIG_HOME", repo.join("no-user-config"))
+        .output()
+        .expect("failed to run agent-router-mcp");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!output.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains(".agent-router/rules.json#/rules/0/conditions/file_pattern: error"),
+        "{}",
+        stdout
+    );
+    let _ = std::fs::remove_dir_all(&repo);
+}
+
+#[test]
+fn test_rule_test_cli_runs_fixtures() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_agent-router-mcp"))
+        .args([
+            "test",
+            "--agents",
+            "config/agents.json",
+            "--rules",
+            "config/rules.json",
+            "--tags",
+            "config/llm-tags.json",
+            "tests/fixtures/rules",
+        ])
+        .output()
+        .expect("failed to run agent-router-mcp");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "rule tests failed:\n{}", stdout);
+    assert!(stdout.contains("3 passed, 0 failed"), "{}", stdout);
+}
+
+#[tokio::test]
+async fn test_get_config_schema_tool() {
+    let handler = create_test_handler();
+    let call = |config: &str| CallToolRequestParams {
+        name: "get_config_schema".to_string(),
+        arguments: Some(json!({"config": config}).as_object().unwrap().clone()),
+        meta: None,
+        task: None,
+    };
+
+    let result = handler
+        .handle_call_tool_request(call("rules"), create_mock_runtime())
+        .await
+        .expect("get_config_schema failed");
+    let schema: serde_json::Value = match &result.content[0] {
+        ContentBlock::TextContent(text) => serde_json::from_str(&text.text).unwrap(),
+        other => panic!("Expected text content, got {:?}", other),
+    };
+    assert_eq!(schema, config_schema(ConfigKind::Rules));
+    assert!(schema["$defs"]["RuleConditions"].is_object());
+
+    let result = handler
+        .handle_call_tool_request(call("workflows"), create_mock_runtime())
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_reload_config_tool() {
+    let handler = create_test_handler();
+    handler.state.lock().await.config = Config {
+        backend: Backend::Ollama,
+        ollama_url: "http://localhost:1".to_string(),
+        routing_mode: RoutingMode::RulesOnly,
+        ..Config::default()
+    };
+    let call = |name: &str, arguments: serde_json::Value| CallToolRequestParams {
+        name: name.to_string(),
+        arguments: Some(arguments.as_object().unwrap().clone()),
+        meta: None,
+        task: None,
+    };
+    let text = |result: CallToolResult| match &result.content[0] {
+        ContentBlock::TextContent(text) => text.text.clone(),
+        other => panic!("Expected text content, got {:?}", other),
+    };
+
+    // Before any routing request the files are only checked
+    let result = handler
+        .handle_call_tool_request(call("reload_config", json!({})), create_mock_runtime())
+        .await
+        .expect("reload_config failed");
+    let output: serde_json::Value = serde_json::from_str(&text(result)).unwrap();
+    assert_eq!(output["reloaded"], false);
+    for config in ["agents", "llm_tags", "rules"] {
+        assert!(output["sources"][config]["source"].is_string());
+    }
+
+    handler
+        .handle_call_tool_request(
+            call(
+                "get_instructions",
+                json!({"task": "Fix the parser", "intent": "help debug an issue"}),
+            ),
+            create_mock_runtime(),
+        )
+        .await
+        .expect("get_instructions failed");
+
+    let result = handler
+        .handle_call_tool_request(call("reload_config", json!({})), create_mock_runtime())
+        .await
+        .expect("reload_config failed");
+    let output: serde_json::Value = serde_json::from_str(&text(result)).unwrap();
+    assert_eq!(output["reloaded"], true);
+    assert_eq!(output["summary"], "no changes");
+}
+
+#[tokio::test]
+async fn test_explain_routing_returns_trace() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let mock_server = MockServer::start().await;
+    let reply = r#"{"tags": [{"name": "security-concern", "confidence": 90}]}"#;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{"message": {"role": "assistant", "content": reply}}]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let handler = create_test_handler();
+    handler.state.lock().await.config = Config {
+        backend: Backend::OpenAi,
+        openai_base_url: mock_server.uri(),
+        openai_api_key: None,
+        routing_mode: RoutingMode::Auto,
+        ..Config::default()
+    };
+    let runtime = create_mock_runtime();
+
+    let params = CallToolRequestParams {
+        name: "explain_routing".to_string(),
+        arguments: Some(
+            json!({
+                "task": "Add OAuth login",
+                "intent": "implement feature",
+                "associated_files": ["src/auth.rs"]
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ),
+        meta: None,
+        task: None,
+    };
+
+    let result = handler
+        .handle_call_tool_request(params, runtime)
+        .await
+        .expect("explain_routing failed");
+    let text = match &result.content[0] {
+        ContentBlock::TextContent(text) => text.text.clone(),
+        other => panic!("Expected text content, got {:?}", other),
+    };
+    let output: serde_json::Value = serde_json::from_str(&text).unwrap();
+
+    assert_eq!(
+        output["llm"]["raw_responses"][0], reply,
+        "Unexpected: {}",
+        text
+    );
+    assert_eq!(output["llm"]["tags"][0]["tag"], "security-concern");
+
+    let agents: Vec<&str> = output["response"]["instructions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["route_to_agent"]["name"].as_str().unwrap())
+        .collect();
+    assert!(agents.contains(&"language-reviewer-rust"));
+    assert!(agents.contains(&"security-auditor"));
+
+    let rust_rule = output["rules"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["conditions"]["value"] == "*.rs")
+        .expect("Rust rule missing from trace");
+    assert_eq!(rust_rule["matched"], true);
+    assert_eq!(
+        rust_rule["conditions"]["matched_files"],
+        json!(["src/auth.rs"])
+    );
+
+    let dropped = output["dropped_agents"].as_array().unwrap();
+    assert!(dropped
+        .iter()
+        .any(|d| d["agent"] == "language-reviewer-typescript"
+            && d["reason"] == "No rule routing to this agent matched"));
+}
+
 #[tokio::test]
 async fn test_unknown_tool_returns_error() {
     let handler = create_test_handler();
@@ -199,10 +650,69 @@ async fn test_concurrent_tool_calls() {
     for handle in handles {
         let result = handle.await.expect("Task panicked");
         assert!(result.is_ok());
-        assert_eq!(result.unwrap().tools.len(), 2);
+        assert_eq!(result.unwrap().tools.len(), 5);
     }
 }
 
+#[tokio::test]
+async fn test_lmstudio_backend_identifies_tags() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{"message": {"role": "assistant", "content": "2"}}]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config {
+        backend: Backend::LmStudio,
+        lmstudio_url: mock_server.uri(),
+        model_name: "unsloth/SmolLM3-3B-128K-GGUF".to_string(),
+        ..Config::default()
+    };
+    let manager = ModelManager::new(config).expect("Failed to create model manager");
+    assert_eq!(manager.backend_name(), "LM Studio");
+
+    let input = ClassificationInput {
+        task: "Add OAuth login".to_string(),
+        intent: "implement feature".to_string(),
+        original_prompt: None,
+        associated_files: None,
+        git_context: None,
+        agent_config_path: None,
+        rules_config_path: None,
+        llm_tags_path: None,
+    };
+    let tag_config = LlmTagConfig {
+        tags: vec![
+            LlmTagDefinition {
+                name: "performance".to_string(),
+                description: "Speed and efficiency work".to_string(),
+                examples: vec![],
+                min_confidence: None,
+            },
+            LlmTagDefinition {
+                name: "security".to_string(),
+                description: "Authentication and authorization".to_string(),
+                examples: vec![],
+                min_confidence: None,
+            },
+        ],
+    };
+
+    let tags = manager
+        .identify_tags(&input, &tag_config)
+        .await
+        .expect("Tagging failed");
+    assert_eq!(tags.len(), 1);
+    assert_eq!(tags[0].tag, "security");
+}
+
 // Helper to create a mock MCP runtime
 fn create_mock_runtime() -> Arc<dyn rust_mcp_sdk::McpServer> {
     use rust_mcp_sdk::error::SdkResult;